@@ -0,0 +1,627 @@
+use std::collections::HashSet;
+
+use rand::seq::{IndexedRandom, IteratorRandom};
+
+use crate::controller::BoardView;
+use crate::difficulty::Difficulty;
+use crate::food::{Food, FoodKind};
+use crate::level::Level;
+use crate::rng::GameRng;
+use crate::score::Score;
+use crate::snake::{Direction, Snake};
+use crate::world::{
+    apply_food, initial_food, pay_out_growth, BoardMode, GameState, Grid, StepOutcome, FOOD_SLOTS,
+};
+
+/// How a match between several snakes ended.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MatchResult {
+    /// The player with this index was the last one alive.
+    Winner(usize),
+    /// Every remaining snake died on the same tick.
+    Draw,
+}
+
+/// One snake on a shared board together with its own score.
+pub struct Player {
+    snake: Snake,
+    score: Score,
+    growth: u32,
+    alive: bool,
+}
+
+impl Player {
+    fn new(snake: Snake) -> Player {
+        Player {
+            snake,
+            score: Score::new(),
+            growth: 0,
+            alive: true,
+        }
+    }
+
+    pub fn snake(&self) -> &Snake {
+        &self.snake
+    }
+
+    pub fn score(&self) -> &Score {
+        &self.score
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+}
+
+/// Which living snake covers each cell of the board.
+///
+/// Every snake tracks its own segments; this answers "who is on (x, y)" for
+/// all of them at once. The arena refreshes it after every tick.
+pub struct Occupancy {
+    width: i32,
+    height: i32,
+    cells: Vec<Option<usize>>,
+}
+
+impl Occupancy {
+    pub fn new(width: i32, height: i32) -> Occupancy {
+        Occupancy {
+            width,
+            height,
+            cells: vec![None; (width * height).max(0) as usize],
+        }
+    }
+
+    /// The index of the player on (x, y), if any.
+    pub fn owner(&self, x: i32, y: i32) -> Option<usize> {
+        self.index(x, y).and_then(|index| self.cells[index])
+    }
+
+    pub fn is_occupied(&self, x: i32, y: i32) -> bool {
+        self.owner(x, y).is_some()
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let inside = x >= 0 && y >= 0 && x < self.width && y < self.height;
+        inside.then(|| (y * self.width + x) as usize)
+    }
+
+    /// Refills the index from the living players' current bodies.
+    fn rebuild(&mut self, players: &[Player]) {
+        self.cells.fill(None);
+        for (owner, player) in players.iter().enumerate().filter(|(_, player)| player.alive) {
+            for (x, y) in player.snake.body_positions() {
+                if let Some(index) = self.index(x, y) {
+                    self.cells[index] = Some(owner);
+                }
+            }
+        }
+    }
+}
+
+/// The rules of a match between any number of snakes on one board.
+///
+/// All snakes move on the same tick, each steered by its own controller
+/// through [`Arena::step_with`]. Collisions are resolved against where every
+/// snake was at the start of the tick:
+///
+/// - a snake dies where a single-player snake would, and when its head
+///   enters a cell covered by any other living snake;
+/// - when several heads enter the same cell, the strictly longest snake
+///   survives and the others die; if the longest are tied they all die;
+/// - dead snakes leave the board at once.
+///
+/// The last snake alive wins. If all remaining snakes die on the same tick
+/// the match is a draw. Food is eaten in player order, which never matters
+/// since no two survivors share a cell.
+pub struct Arena {
+    players: Vec<Player>,
+    occupancy: Occupancy,
+    foods: Vec<Food>,
+    speed: i32,
+    grid: Grid,
+    level: Level,
+    walls: HashSet<(i32, i32)>,
+    mode: BoardMode,
+    difficulty: Difficulty,
+    state: GameState,
+    result: Option<MatchResult>,
+    rng: GameRng,
+    tick: u64,
+}
+
+impl Arena {
+    /// Sets up a match for `players` snakes placed by [`Level::spawn_snakes`].
+    /// Returns `None` for fewer than two players, or if any snake would start
+    /// on a wall, the border or another snake.
+    pub fn new(
+        level: Level,
+        players: usize,
+        rng: GameRng,
+        mode: BoardMode,
+        difficulty: Difficulty,
+    ) -> Option<Arena> {
+        if players < 2 {
+            return None;
+        }
+        let grid = Grid::new(level.width, level.height);
+        let walls: HashSet<(i32, i32)> = level.walls.iter().copied().collect();
+        let players: Vec<Player> = level.spawn_snakes(players).into_iter().map(Player::new).collect();
+        let mut taken = HashSet::new();
+        for (x, y) in players.iter().flat_map(|player| player.snake.body_positions()) {
+            if !grid.is_inside(x, y) || walls.contains(&(x, y)) || !taken.insert((x, y)) {
+                return None;
+            }
+        }
+
+        let mut arena = Arena {
+            players,
+            occupancy: Occupancy::new(level.width, level.height),
+            foods: Vec::new(),
+            speed: 0,
+            grid,
+            level,
+            walls,
+            mode,
+            difficulty,
+            state: GameState::Menu,
+            result: None,
+            rng,
+            tick: 0,
+        };
+        arena.reset_board();
+        Some(arena)
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    pub fn occupancy(&self) -> &Occupancy {
+        &self.occupancy
+    }
+
+    /// What the controller of `player` sees of the board: its own snake and
+    /// every other living one.
+    pub fn view(&self, player: usize) -> BoardView<'_> {
+        let rivals = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(index, rival)| *index != player && rival.alive)
+            .map(|(_, rival)| &rival.snake)
+            .collect();
+        BoardView::new(
+            &self.grid,
+            self.mode,
+            &self.walls,
+            &self.players[player].snake,
+            rivals,
+            &self.foods,
+            self.tick,
+        )
+    }
+
+    pub fn foods(&self) -> &[Food] {
+        &self.foods
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// Whether (x, y) is one of the level's interior walls.
+    pub fn is_wall(&self, x: i32, y: i32) -> bool {
+        self.walls.contains(&(x, y))
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Who won, once the match is over.
+    pub fn result(&self) -> Option<MatchResult> {
+        self.result
+    }
+
+    pub fn mode(&self) -> BoardMode {
+        self.mode
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Speed food is shared: whoever eats it changes the pace for everyone.
+    pub fn speed_level(&self) -> i32 {
+        self.speed
+    }
+
+    /// The highest score on the board, which paces the speed curve.
+    pub fn top_points(&self) -> u32 {
+        self.players.iter().map(|player| player.score.points()).max().unwrap_or(0)
+    }
+
+    /// Leaves the menu and starts playing. Does nothing in any other state.
+    pub fn start(&mut self) {
+        if self.state == GameState::Menu {
+            self.state = GameState::Playing;
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.state = match self.state {
+            GameState::Playing => GameState::Paused,
+            GameState::Paused => GameState::Playing,
+            ref other => other.clone(),
+        };
+    }
+
+    /// Tops the board up to [`FOOD_SLOTS`] foods.
+    fn replenish_food(&mut self) {
+        if self.state != GameState::Playing {
+            return;
+        }
+        while self.foods.len() < FOOD_SLOTS && self.add_food() {}
+    }
+
+    /// Moves every living snake one cell with an explicit turn per player,
+    /// and returns what happened to each player in order. Missing entries go
+    /// straight, and a turn into the opposite direction is ignored.
+    pub fn step_with(&mut self, turns: &[Option<Direction>]) -> Vec<StepOutcome> {
+        if self.state != GameState::Playing {
+            return vec![StepOutcome::Idle; self.players.len()];
+        }
+
+        self.tick += 1;
+
+        let turns: Vec<Option<Direction>> = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(index, player)| {
+                let heading = player.snake.head_direction();
+                turns
+                    .get(index)
+                    .copied()
+                    .flatten()
+                    .filter(|dir| *dir != heading && *dir != heading.opposite())
+            })
+            .collect();
+        let heads: Vec<(i32, i32)> = self
+            .players
+            .iter()
+            .zip(&turns)
+            .map(|(player, turn)| player.snake.next_head(*turn))
+            .collect();
+        let deaths: Vec<bool> = (0..self.players.len())
+            .map(|index| self.players[index].alive && self.crashes(index, &heads))
+            .collect();
+
+        let mut outcomes = Vec::with_capacity(self.players.len());
+        for (index, turn) in turns.into_iter().enumerate() {
+            if !self.players[index].alive {
+                outcomes.push(StepOutcome::Idle);
+            } else if deaths[index] {
+                self.players[index].alive = false;
+                outcomes.push(StepOutcome::Died);
+            } else {
+                self.players[index].snake.move_forward(turn);
+                outcomes.push(match self.check_eating(index) {
+                    Some(kind) => StepOutcome::Ate(kind),
+                    None => StepOutcome::Moved,
+                });
+            }
+        }
+        self.occupancy.rebuild(&self.players);
+
+        let mut alive = (0..self.players.len()).filter(|index| self.players[*index].alive);
+        match (alive.next(), alive.next()) {
+            (None, _) => self.finish(MatchResult::Draw),
+            (Some(winner), None) => self.finish(MatchResult::Winner(winner)),
+            (Some(_), Some(_)) => {}
+        }
+        let tick = self.tick;
+        self.foods.retain(|food| !food.is_expired(tick));
+        self.replenish_food();
+        outcomes
+    }
+
+    /// Starts a new match from the same spawns, keeping the random sequence going.
+    pub fn restart(&mut self) {
+        let count = self.players.len();
+        self.players = self.level.spawn_snakes(count).into_iter().map(Player::new).collect();
+        self.speed = 0;
+        self.state = GameState::Playing;
+        self.result = None;
+        self.tick = 0;
+        self.reset_board();
+    }
+
+    /// Applies the board mode to every snake and lays out the level's first
+    /// food, unless a snake starts on it.
+    fn reset_board(&mut self) {
+        let wrap = match self.mode {
+            BoardMode::Walls => None,
+            BoardMode::Wrap => Some(self.grid.wrap_bounds()),
+        };
+        for player in &mut self.players {
+            player.snake.set_wrap(wrap);
+        }
+        self.occupancy.rebuild(&self.players);
+        let occupancy = &self.occupancy;
+        self.foods = self
+            .level
+            .food
+            .filter(|&(x, y)| !occupancy.is_occupied(x, y))
+            .map(initial_food)
+            .into_iter()
+            .collect();
+    }
+
+    fn finish(&mut self, result: MatchResult) {
+        let final_score = match result {
+            MatchResult::Winner(index) => self.players[index].score.points(),
+            MatchResult::Draw => self.top_points(),
+        };
+        self.result = Some(result);
+        self.state = GameState::GameOver { final_score };
+    }
+
+    /// Whether player `index` dies this tick, given where every head is going.
+    fn crashes(&self, index: usize, heads: &[(i32, i32)]) -> bool {
+        let (x, y) = heads[index];
+        // The next head is never the snake's own head, so any occupied cell is a body.
+        if !self.grid.is_inside(x, y) || self.walls.contains(&(x, y)) || self.occupancy.is_occupied(x, y) {
+            return true;
+        }
+
+        let length = self.players[index].snake.len();
+        self.players.iter().enumerate().any(|(other, rival)| {
+            other != index && rival.alive && heads[other] == (x, y) && rival.snake.len() >= length
+        })
+    }
+
+    fn check_eating(&mut self, index: usize) -> Option<FoodKind> {
+        let player = &mut self.players[index];
+        let head = player.snake.head_position();
+        let eaten = self
+            .foods
+            .iter()
+            .position(|food| food.position() == head)
+            .map(|position| self.foods.remove(position));
+
+        if let Some(food) = &eaten {
+            player.score.eat(food, self.tick, self.speed);
+            apply_food(food.kind(), &mut player.snake, &mut player.growth, &mut self.speed);
+        }
+
+        pay_out_growth(&mut player.snake, &mut player.growth);
+        eaten.map(|food| food.kind())
+    }
+
+    fn add_food(&mut self) -> bool {
+        let occupancy = &self.occupancy;
+        let walls = &self.walls;
+        let foods = &self.foods;
+        let choice = (1..self.grid.width - 1)
+            .flat_map(|x| (1..self.grid.height - 1).map(move |y| (x, y)))
+            .filter(|(x, y)| !occupancy.is_occupied(*x, *y))
+            .filter(|cell| !walls.contains(cell))
+            .filter(|cell| foods.iter().all(|food| food.position() != *cell))
+            .choose(&mut self.rng);
+
+        let Some((x, y)) = choice else {
+            return false;
+        };
+        let kind = *FoodKind::ALL
+            .choose_weighted(&mut self.rng, FoodKind::spawn_weight)
+            .expect("food weights are positive");
+        let expires_at = kind.lifetime().map(|lifetime| self.tick + lifetime);
+        self.foods.push(Food::new(kind, x, y, expires_at));
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn playing(width: i32, height: i32, players: usize) -> Arena {
+        let level = Level::empty(width, height);
+        let mut arena =
+            Arena::new(level, players, GameRng::new(0), BoardMode::Walls, Difficulty::Normal).unwrap();
+        arena.start();
+        arena
+    }
+
+    /// Replaces player `index`'s snake, keeping the occupancy index in sync.
+    fn place(arena: &mut Arena, index: usize, body: &[(i32, i32)], direction: Direction) {
+        arena.players[index].snake = Snake::from_parts(body, direction);
+        arena.occupancy.rebuild(&arena.players);
+    }
+
+    /// Runs `arena` until the match ends, with no further input.
+    fn play_out(arena: &mut Arena) {
+        for _ in 0..1000 {
+            if arena.result().is_some() {
+                return;
+            }
+            arena.step_with(&[]);
+        }
+        panic!("match never ended");
+    }
+
+    #[test]
+    fn players_start_mirrored() {
+        let arena = playing(15, 15, 2);
+        let [one, two] = arena.players() else {
+            panic!("expected two players");
+        };
+        assert_eq!(one.snake().head_position(), (4, 2));
+        assert_eq!(two.snake().head_position(), (10, 12));
+        assert_eq!(two.snake().head_direction(), Direction::Left);
+    }
+
+    #[test]
+    fn board_too_small_for_all_snakes_is_rejected() {
+        let new = |width, height, players| {
+            Arena::new(Level::empty(width, height), players, GameRng::new(0), BoardMode::Walls, Difficulty::Normal)
+        };
+        assert!(new(7, 5, 2).is_none());
+        assert!(new(15, 15, 1).is_none());
+        assert!(new(15, 15, 6).is_some());
+        assert!(new(15, 7, 6).is_none());
+    }
+
+    #[test]
+    fn occupancy_tracks_every_snake() {
+        let mut arena = playing(15, 15, 4);
+        for (index, player) in arena.players().iter().enumerate() {
+            for (x, y) in player.snake().body_positions() {
+                assert_eq!(arena.occupancy().owner(x, y), Some(index));
+            }
+        }
+        arena.foods.clear();
+        arena.step_with(&[]);
+        assert_eq!(arena.occupancy().owner(5, 2), Some(0));
+        assert_eq!(arena.occupancy().owner(2, 2), None);
+    }
+
+    #[test]
+    fn step_with_takes_one_turn_per_player() {
+        let mut arena = playing(15, 15, 3);
+        arena.step_with(&[None, Some(Direction::Up)]);
+        let heads: Vec<(i32, i32)> = arena.players().iter().map(|p| p.snake().head_position()).collect();
+        assert_eq!(heads, vec![(5, 2), (10, 11), (5, 9)]);
+    }
+
+    #[test]
+    fn equal_heads_meeting_is_a_draw() {
+        let mut arena = playing(15, 15, 2);
+        arena.foods.clear();
+        place(&mut arena, 0, &[(6, 7), (5, 7), (4, 7)], Direction::Right);
+        place(&mut arena, 1, &[(8, 7), (9, 7), (10, 7)], Direction::Left);
+        assert_eq!(arena.step_with(&[]), vec![StepOutcome::Died, StepOutcome::Died]);
+        assert_eq!(arena.result(), Some(MatchResult::Draw));
+    }
+
+    #[test]
+    fn longer_snake_wins_a_head_on() {
+        let mut arena = playing(15, 15, 3);
+        arena.foods.clear();
+        place(&mut arena, 0, &[(6, 5), (5, 5), (4, 5), (3, 5)], Direction::Right);
+        place(&mut arena, 1, &[(8, 5), (9, 5), (10, 5)], Direction::Left);
+        place(&mut arena, 2, &[(7, 7), (7, 8), (7, 9)], Direction::Up);
+        let outcomes = arena.step_with(&[]);
+        assert_eq!(outcomes[1], StepOutcome::Died);
+        assert_ne!(outcomes[0], StepOutcome::Died);
+        assert_ne!(outcomes[2], StepOutcome::Died);
+        assert_eq!(arena.players()[0].snake().head_position(), (7, 5));
+        assert_eq!(arena.result(), None);
+    }
+
+    #[test]
+    fn tied_longest_snakes_die_together_in_a_three_way() {
+        let mut arena = playing(15, 15, 3);
+        arena.foods.clear();
+        place(&mut arena, 0, &[(6, 5), (5, 5), (4, 5), (3, 5)], Direction::Right);
+        place(&mut arena, 1, &[(8, 5), (9, 5), (10, 5), (11, 5)], Direction::Left);
+        place(&mut arena, 2, &[(7, 6), (7, 7), (7, 8)], Direction::Up);
+        assert_eq!(arena.step_with(&[]), vec![StepOutcome::Died; 3]);
+        assert_eq!(arena.result(), Some(MatchResult::Draw));
+    }
+
+    #[test]
+    fn running_into_another_body_loses() {
+        let mut arena = playing(15, 15, 2);
+        // A long second snake runs along row 4, right below player one.
+        let body: Vec<(i32, i32)> = (1..=9).rev().map(|x| (x, 4)).collect();
+        place(&mut arena, 1, &body, Direction::Right);
+        arena.step_with(&[Some(Direction::Down)]);
+        assert_eq!(arena.step_with(&[])[0], StepOutcome::Died);
+        assert_eq!(arena.result(), Some(MatchResult::Winner(1)));
+        assert!(matches!(arena.state(), GameState::GameOver { .. }));
+    }
+
+    #[test]
+    fn dead_snakes_leave_the_board() {
+        let mut arena = playing(15, 15, 3);
+        arena.foods.clear();
+        arena.step_with(&[Some(Direction::Up)]);
+        arena.step_with(&[]);
+        assert!(!arena.players()[0].is_alive());
+        assert_eq!(arena.occupancy().owner(4, 1), None);
+        assert_eq!(arena.result(), None);
+    }
+
+    #[test]
+    fn both_hitting_walls_on_the_same_tick_is_a_draw() {
+        let mut arena = playing(15, 15, 2);
+        arena.foods.clear();
+        arena.step_with(&[Some(Direction::Up), Some(Direction::Down)]);
+        play_out(&mut arena);
+        assert_eq!(arena.result(), Some(MatchResult::Draw));
+    }
+
+    #[test]
+    fn players_score_separately() {
+        let mut arena = playing(15, 15, 2);
+        arena.foods = vec![Food::new(FoodKind::Normal, 5, 2, None)];
+        let outcomes = arena.step_with(&[]);
+        assert_eq!(outcomes, vec![StepOutcome::Ate(FoodKind::Normal), StepOutcome::Moved]);
+        assert_eq!(arena.players()[0].score().points(), FoodKind::Normal.points());
+        assert_eq!(arena.players()[1].score().points(), 0);
+    }
+
+    #[test]
+    fn food_never_spawns_on_a_snake() {
+        let mut arena = playing(15, 15, 4);
+        for _ in 0..50 {
+            arena.foods.clear();
+            arena.replenish_food();
+            for food in arena.foods() {
+                let (x, y) = food.position();
+                assert!(!arena.occupancy().is_occupied(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_plays_the_same_match() {
+        let run = || {
+            let mut arena = playing(15, 15, 4);
+            let mut log = Vec::new();
+            while arena.result().is_none() {
+                let outcomes = arena.step_with(&[]);
+                let foods: Vec<(i32, i32)> = arena.foods().iter().map(Food::position).collect();
+                log.push((outcomes, foods));
+            }
+            (log, arena.result())
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn restart_brings_every_player_back() {
+        let mut arena = playing(15, 15, 3);
+        play_out(&mut arena);
+        arena.restart();
+        assert_eq!(*arena.state(), GameState::Playing);
+        assert_eq!(arena.result(), None);
+        assert_eq!(arena.players().len(), 3);
+        assert!(arena.players().iter().all(Player::is_alive));
+    }
+}
@@ -1,15 +1,24 @@
 use std::time::Duration;
 use rodio::{OutputStream, OutputStreamBuilder, Sink, Source};
 use rodio::source::SineWave;
+use snake::food::FoodKind;
+
+/// Loudness of every tone at full volume.
+const AMPLITUDE: f32 = 0.3;
 
 pub struct SoundPlayer {
     stream: OutputStream,
+    /// 0.0 (silent) to 1.0 (full).
+    volume: f32,
 }
 
 impl SoundPlayer {
-    pub fn new() -> Option<Self> {
+    pub fn new(volume: f32) -> Option<Self> {
         match OutputStreamBuilder::open_default_stream() {
-            Ok(stream) => Some(SoundPlayer { stream }),
+            Ok(stream) => Some(SoundPlayer {
+                stream,
+                volume: volume.clamp(0.0, 1.0),
+            }),
             Err(e) => {
                 eprintln!("Failed to initialize audio: {}", e);
                 None
@@ -17,22 +26,41 @@ impl SoundPlayer {
         }
     }
 
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
     /// Play a short high beep when eating food (880Hz, 50ms)
     pub fn play_eat(&self) {
         let source = SineWave::new(880.0)
             .take_duration(Duration::from_millis(50))
-            .amplify(0.3);
+            .amplify(AMPLITUDE * self.volume);
         self.play_source(source);
     }
 
+    /// Play the sound for eating a given kind of food
+    pub fn play_food(&self, kind: FoodKind) {
+        match kind {
+            FoodKind::Normal => self.play_eat(),
+            FoodKind::Bonus => self.play_tones(&[(880.0, 50), (1320.0, 80)]),
+            FoodKind::Shrink => self.play_tones(&[(660.0, 60), (440.0, 60)]),
+            FoodKind::SpeedUp => self.play_tones(&[(990.0, 40), (1180.0, 40), (1400.0, 40)]),
+            FoodKind::SlowDown => self.play_tones(&[(520.0, 80), (390.0, 120)]),
+        }
+    }
+
     /// Play a descending tone on game over (440Hz -> 220Hz effect via two tones)
     pub fn play_death(&self) {
         let tone1 = SineWave::new(440.0)
             .take_duration(Duration::from_millis(150))
-            .amplify(0.3);
+            .amplify(AMPLITUDE * self.volume);
         let tone2 = SineWave::new(220.0)
             .take_duration(Duration::from_millis(200))
-            .amplify(0.3);
+            .amplify(AMPLITUDE * self.volume);
 
         let sink = Sink::connect_new(self.stream.mixer());
         sink.append(tone1);
@@ -40,14 +68,19 @@ impl SoundPlayer {
         sink.detach();
     }
 
+    /// Play a rising fanfare when the snake fills the board
+    pub fn play_victory(&self) {
+        self.play_tones(&[(523.0, 100), (659.0, 100), (784.0, 100), (1046.0, 300)]);
+    }
+
     /// Play a rising tone on game start (330Hz -> 660Hz effect via two tones)
     pub fn play_start(&self) {
         let tone1 = SineWave::new(330.0)
             .take_duration(Duration::from_millis(100))
-            .amplify(0.3);
+            .amplify(AMPLITUDE * self.volume);
         let tone2 = SineWave::new(660.0)
             .take_duration(Duration::from_millis(150))
-            .amplify(0.3);
+            .amplify(AMPLITUDE * self.volume);
 
         let sink = Sink::connect_new(self.stream.mixer());
         sink.append(tone1);
@@ -55,6 +88,19 @@ impl SoundPlayer {
         sink.detach();
     }
 
+    /// Play a sequence of (frequency Hz, duration ms) tones back to back
+    fn play_tones(&self, tones: &[(f32, u64)]) {
+        let sink = Sink::connect_new(self.stream.mixer());
+        for &(freq, millis) in tones {
+            sink.append(
+                SineWave::new(freq)
+                    .take_duration(Duration::from_millis(millis))
+                    .amplify(AMPLITUDE * self.volume),
+            );
+        }
+        sink.detach();
+    }
+
     fn play_source<S>(&self, source: S)
     where
         S: Source<Item = f32> + Send + 'static,
@@ -0,0 +1,217 @@
+use std::collections::VecDeque;
+
+use crate::controller::{turn_left, turn_right, BoardView, Controller};
+use crate::snake::Direction;
+
+/// Plays on its own: follows the shortest path to the nearest food, but only
+/// takes a step after which the snake could still reach its own tail, or
+/// at least has as many free cells around it as it is long. When no food is
+/// safely reachable it stalls in the roomiest safe direction.
+///
+/// Needs nothing but a [`BoardView`], so it runs the same in the window, as
+/// a toggleable autopilot, and in headless simulations.
+#[derive(Default)]
+pub struct Autopilot;
+
+impl Autopilot {
+    pub fn new() -> Autopilot {
+        Autopilot
+    }
+}
+
+impl Controller for Autopilot {
+    fn name(&self) -> &str {
+        "autopilot"
+    }
+
+    fn next_turn(&mut self, view: &BoardView) -> Option<Direction> {
+        let heading = view.snake().head_direction();
+        let choice = path_to_food(view)
+            .filter(|dir| room_after(view, *dir).is_safe(view.snake().len()))
+            .or_else(|| {
+                view.safe_directions()
+                    .into_iter()
+                    .map(|dir| (dir, room_after(view, dir)))
+                    .max_by_key(|(_, room)| (room.is_safe(view.snake().len()), room.cells))
+                    .map(|(dir, _)| dir)
+            });
+        choice.filter(|dir| *dir != heading)
+    }
+}
+
+/// Candidate directions in the order they are tried: straight ahead first.
+fn directions(heading: Direction) -> [Direction; 3] {
+    [heading, turn_left(heading), turn_right(heading)]
+}
+
+/// What the head would have around it after a move.
+struct Room {
+    cells: usize,
+    reaches_tail: bool,
+}
+
+impl Room {
+    /// The snake can keep moving: it can chase its own tail, or has at
+    /// least as many cells to fill as it has segments.
+    fn is_safe(&self, length: usize) -> bool {
+        self.reaches_tail || self.cells >= length
+    }
+}
+
+/// Flood-fills the board from the cell the head reaches by moving in
+/// `direction`. The tail counts as free since it moves away on the same tick.
+fn room_after(view: &BoardView, direction: Direction) -> Room {
+    let snake = view.snake();
+    let head = view.neighbour(snake.head_position(), direction);
+    let tail = *snake.body_positions().last().expect("a snake has a body");
+    let mut visited = Visited::new(view);
+    visited.insert(head);
+
+    let mut queue = VecDeque::from([head]);
+    let mut room = Room {
+        cells: 0,
+        reaches_tail: false,
+    };
+    while let Some(cell) = queue.pop_front() {
+        for dir in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+            let next = view.neighbour(cell, dir);
+            if !passable(view, next, tail) || !visited.insert(next) {
+                continue;
+            }
+            room.cells += 1;
+            room.reaches_tail |= next == tail;
+            queue.push_back(next);
+        }
+    }
+    room
+}
+
+/// The first step of a shortest path from the head to the nearest food.
+fn path_to_food(view: &BoardView) -> Option<Direction> {
+    let snake = view.snake();
+    let tail = *snake.body_positions().last().expect("a snake has a body");
+    let mut visited = Visited::new(view);
+    visited.insert(snake.head_position());
+
+    let mut queue = VecDeque::new();
+    for dir in directions(snake.head_direction()) {
+        let next = view.neighbour(snake.head_position(), dir);
+        if view.is_free(next.0, next.1) && visited.insert(next) {
+            queue.push_back((next, dir));
+        }
+    }
+    while let Some((cell, first)) = queue.pop_front() {
+        if view.foods().iter().any(|food| food.position() == cell) {
+            return Some(first);
+        }
+        for dir in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+            let next = view.neighbour(cell, dir);
+            if passable(view, next, tail) && visited.insert(next) {
+                queue.push_back((next, first));
+            }
+        }
+    }
+    None
+}
+
+fn passable(view: &BoardView, (x, y): (i32, i32), tail: (i32, i32)) -> bool {
+    view.is_free(x, y) || (x, y) == tail
+}
+
+/// One flag per board cell.
+struct Visited {
+    width: i32,
+    cells: Vec<bool>,
+}
+
+impl Visited {
+    fn new(view: &BoardView) -> Visited {
+        let grid = view.grid();
+        Visited {
+            width: grid.width,
+            cells: vec![false; (grid.width * grid.height).max(0) as usize],
+        }
+    }
+
+    /// Marks (x, y) and returns whether it was new. Cells off the board
+    /// never are.
+    fn insert(&mut self, (x, y): (i32, i32)) -> bool {
+        let index = (y * self.width + x) as usize;
+        if x < 0 || y < 0 || x >= self.width || index >= self.cells.len() || self.cells[index] {
+            return false;
+        }
+        self.cells[index] = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::level::Level;
+    use crate::rng::GameRng;
+    use crate::world::{GameState, World};
+
+    fn play(mut world: World, ticks: u32) -> World {
+        let mut autopilot = Autopilot::new();
+        world.start();
+        for _ in 0..ticks {
+            if *world.state() != GameState::Playing {
+                break;
+            }
+            let turn = autopilot.next_turn(&world.view());
+            world.step_with(turn);
+        }
+        world
+    }
+
+    #[test]
+    fn heads_straight_for_food() {
+        let mut world = World::new(15, 15, 0);
+        world.set_food_position(4, 6);
+        world.start();
+        assert_eq!(Autopilot::new().next_turn(&world.view()), Some(Direction::Down));
+    }
+
+    #[test]
+    fn survives_long_runs_and_grows() {
+        let world = play(World::new(12, 12, 5), 2000);
+        assert!(world.snake().len() >= 15, "only grew to {}", world.snake().len());
+    }
+
+    #[test]
+    fn finds_its_way_around_walls() {
+        let level = Level::parse(include_str!("../levels/pillars.txt")).unwrap();
+        let world = play(World::with_level(level, GameRng::new(1)), 1000);
+        assert!(world.score().points() > 0);
+    }
+
+    #[test]
+    fn tail_counts_as_free_room() {
+        // 13 x 13 playable cells, minus the body and the cell the head moves to,
+        // plus the tail that moves away.
+        let world = World::new(15, 15, 0);
+        let room = room_after(&world.view(), Direction::Right);
+        assert_eq!(room.cells, 13 * 13 - 3 - 1 + 1);
+        assert!(room.reaches_tail);
+    }
+
+    #[test]
+    fn refuses_food_in_a_dead_end() {
+        let level = Level::parse(
+            "\
+direction left
+map
+#########
+####*####
+#...@...#
+#########
+",
+        )
+        .unwrap();
+        let mut world = World::with_level(level, GameRng::new(0));
+        world.start();
+        assert_eq!(path_to_food(&world.view()), Some(Direction::Up));
+        assert_eq!(Autopilot::new().next_turn(&world.view()), None);
+    }
+}
@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+use std::process;
+
+use snake::bots;
+use snake::hamilton;
+use snake::level::{self, Level};
+use snake::rng::GameRng;
+use snake::sim::{self, Format, Summary};
+use snake::world::{BoardMode, World};
+
+const USAGE: &str = "\
+usage: snake-sim [--bot NAME] [--games N] [--size WxH[,WxH...]] [--seed N]
+                 [--max-ticks N] [--mode walls|wrap] [--level PATH] [--format text|csv|json]
+
+hamilton only works on boards with an even width or height and no walls,
+e.g. 16x15 rather than the default 15x15, and plays like autopilot otherwise.";
+
+/// What was asked for on the command line, with the defaults filled in.
+#[derive(Debug, PartialEq)]
+struct Options {
+    bot: String,
+    games: u64,
+    seed: u64,
+    max_ticks: u64,
+    mode: BoardMode,
+    format: Format,
+    level: Option<PathBuf>,
+    sizes: Vec<(i32, i32)>,
+    help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            bot: "autopilot".to_string(),
+            games: 1000,
+            seed: 0,
+            max_ticks: 50_000,
+            mode: BoardMode::Walls,
+            format: Format::Text,
+            level: None,
+            sizes: vec![(15, 15)],
+            help: false,
+        }
+    }
+}
+
+impl Options {
+    /// Reads the arguments after the program name, rejecting unknown flags,
+    /// missing values and anything out of range.
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut sizes = None;
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let flag = flag.as_str();
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", flag));
+            match flag {
+                "-h" | "--help" => options.help = true,
+                "--bot" => {
+                    let name = value()?;
+                    if bots::by_name(&name, 0).is_none() {
+                        return Err(format!("unknown bot {:?}, expected one of {}", name, bots::NAMES.join(", ")));
+                    }
+                    options.bot = name;
+                }
+                "--games" => options.games = number(flag, value()?)?,
+                "--seed" => options.seed = number(flag, value()?)?,
+                "--max-ticks" => options.max_ticks = number(flag, value()?)?,
+                "--mode" => {
+                    let name = value()?;
+                    options.mode = BoardMode::from_name(&name)
+                        .ok_or_else(|| format!("unknown mode {:?}, expected walls or wrap", name))?;
+                }
+                "--format" => {
+                    let name = value()?;
+                    options.format = Format::from_name(&name)
+                        .ok_or_else(|| format!("unknown format {:?}, expected text, csv or json", name))?;
+                }
+                "--level" => options.level = Some(value()?.into()),
+                "--size" => {
+                    let text = value()?;
+                    let parsed = text.split(',').map(|size| parse_size(size.trim()));
+                    sizes = Some(parsed.collect::<Option<Vec<_>>>().ok_or_else(|| {
+                        format!(
+                            "invalid size {:?}, expected WxH from {}x{} to {}x{}",
+                            text,
+                            level::MIN_WIDTH,
+                            level::MIN_HEIGHT,
+                            level::MAX_SIDE,
+                            level::MAX_SIDE
+                        )
+                    })?);
+                }
+                _ => return Err(format!("unknown argument {:?}", flag)),
+            }
+        }
+
+        if let Some(sizes) = sizes {
+            if options.level.is_some() {
+                return Err("--level and --size cannot be combined".to_string());
+            }
+            options.sizes = sizes;
+        }
+        Ok(options)
+    }
+}
+
+/// Runs seeded games with a bot, without a window or audio, and prints
+/// statistics over them.
+fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|message| fail(&message));
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let levels: Vec<Level> = match &options.level {
+        Some(path) => match level::load_from(path) {
+            Ok(level) => vec![level],
+            Err(e) => fail(&format!("failed to load level {}: {}", path.display(), e)),
+        },
+        None => options.sizes.iter().map(|(width, height)| Level::empty(*width, *height)).collect(),
+    };
+
+    let bot = &options.bot;
+    let mut results = Vec::new();
+    for level in &levels {
+        if bot == "hamilton" && !hamilton::has_cycle(level) {
+            eprintln!(
+                "snake-sim: hamilton has no cycle on {}x{} and plays like autopilot; \
+                 it needs an even width or height and no walls",
+                level.width, level.height
+            );
+        }
+        for game in 0..options.games {
+            let seed = options.seed.wrapping_add(game);
+            let mut world = World::with_level(level.clone(), GameRng::new(seed));
+            world.set_mode(options.mode);
+            let mut controller = bots::by_name(bot, seed).expect("bot name was checked");
+            results.push(sim::play(world, controller.as_mut(), options.max_ticks));
+        }
+    }
+    print!("{}", options.format.render(bot, &Summary::by_size(&results)));
+}
+
+fn number(flag: &str, text: String) -> Result<u64, String> {
+    text.parse().map_err(|_| format!("invalid {} {:?}, expected a whole number", flag, text))
+}
+
+/// A board size such as `20x15`, big enough for the snake to spawn on and
+/// small enough to show.
+fn parse_size(text: &str) -> Option<(i32, i32)> {
+    let (width, height) = text.split_once('x')?;
+    let (width, height): (i32, i32) = (width.parse().ok()?, height.parse().ok()?);
+    let fits = |side: i32, min: i32| (min..=level::MAX_SIDE).contains(&side);
+    (fits(width, level::MIN_WIDTH) && fits(height, level::MIN_HEIGHT)).then_some((width, height))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("snake-sim: {}", message);
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_keep_the_defaults() {
+        assert_eq!(parse(&[]), Ok(Options::default()));
+    }
+
+    #[test]
+    fn reads_settings() {
+        let options = parse(&["--bot", "greedy", "--games", "5", "--size", "20x15, 16x16", "--mode", "wrap"]).unwrap();
+        assert_eq!(options.bot, "greedy");
+        assert_eq!(options.games, 5);
+        assert_eq!(options.sizes, vec![(20, 15), (16, 16)]);
+        assert_eq!(options.mode, BoardMode::Wrap);
+    }
+
+    #[test]
+    fn rejects_unknown_flags_and_missing_values() {
+        assert_eq!(parse(&["--game", "5"]), Err("unknown argument \"--game\"".to_string()));
+        assert_eq!(parse(&["--games"]), Err("--games needs a value".to_string()));
+        assert!(parse(&["--games", "many"]).is_err());
+        assert!(parse(&["--bot", "clever"]).is_err());
+    }
+
+    #[test]
+    fn sizes_must_fit_on_screen() {
+        assert!(parse(&["--size", "100x100"]).is_ok());
+        assert!(parse(&["--size", "15x15,101x15"]).is_err());
+        assert!(parse(&["--size", "5x15"]).is_err());
+        assert!(parse(&["--size", "15"]).is_err());
+    }
+
+    #[test]
+    fn level_and_size_do_not_mix() {
+        assert!(parse(&["--level", "maze.txt", "--size", "20x20"]).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use rand::seq::IndexedRandom;
+
+use crate::autopilot::Autopilot;
+use crate::controller::{BoardView, Controller};
+use crate::hamilton::HamiltonBot;
+use crate::rng::GameRng;
+use crate::snake::Direction;
+
+/// Bots that [`by_name`] can build.
+pub const NAMES: [&str; 4] = ["autopilot", "greedy", "hamilton", "random"];
+
+/// Builds the bot called `name`. Bots that make random choices draw them
+/// from `seed`, so a seeded game plays out the same every time.
+pub fn by_name(name: &str, seed: u64) -> Option<Box<dyn Controller>> {
+    match name {
+        "autopilot" => Some(Box::new(Autopilot::new())),
+        "greedy" => Some(Box::new(GreedyBot)),
+        "hamilton" => Some(Box::new(HamiltonBot::new())),
+        "random" => Some(Box::new(RandomBot::new(seed))),
+        _ => None,
+    }
+}
+
+/// Turns the result of picking a direction into the turn a controller
+/// returns: going on straight is `None`.
+fn as_turn(view: &BoardView, direction: Option<Direction>) -> Option<Direction> {
+    direction.filter(|dir| *dir != view.snake().head_direction())
+}
+
+/// Heads for the closest food as the crow flies, taking any move that does
+/// not die on the spot. Easily traps itself in its own coils.
+pub struct GreedyBot;
+
+impl Controller for GreedyBot {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    fn next_turn(&mut self, view: &BoardView) -> Option<Direction> {
+        let head = view.snake().head_position();
+        let distance = |(x, y): (i32, i32), (fx, fy): (i32, i32)| (x - fx).abs() + (y - fy).abs();
+        let target = view
+            .foods()
+            .iter()
+            .map(|food| food.position())
+            .min_by_key(|food| distance(head, *food));
+
+        let safe = view.safe_directions();
+        let best = match target {
+            Some(food) => safe
+                .iter()
+                .copied()
+                .min_by_key(|dir| distance(view.neighbour(head, *dir), food)),
+            None => safe.first().copied(),
+        };
+        as_turn(view, best)
+    }
+}
+
+/// Wanders around, picking any move that does not die on the spot.
+pub struct RandomBot {
+    rng: GameRng,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> RandomBot {
+        RandomBot {
+            rng: GameRng::new(seed),
+        }
+    }
+}
+
+impl Controller for RandomBot {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn next_turn(&mut self, view: &BoardView) -> Option<Direction> {
+        let choice = view.safe_directions().choose(&mut self.rng).copied();
+        as_turn(view, choice)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::{GameState, World};
+
+    /// Lets `bot` play a fresh game until it dies or `ticks` run out.
+    fn play(bot: &mut dyn Controller, ticks: u32) -> World {
+        let mut world = World::new(15, 15, 7);
+        world.start();
+        for _ in 0..ticks {
+            if *world.state() != GameState::Playing {
+                break;
+            }
+            let turn = bot.next_turn(&world.view());
+            world.step_with(turn);
+        }
+        world
+    }
+
+    #[test]
+    fn every_name_builds_a_bot() {
+        for name in NAMES {
+            assert_eq!(by_name(name, 0).unwrap().name(), name);
+        }
+        assert!(by_name("oracle", 0).is_none());
+    }
+
+    #[test]
+    fn greedy_bot_eats() {
+        let world = play(&mut GreedyBot, 200);
+        assert!(world.score().points() > 0);
+    }
+
+    #[test]
+    fn random_bot_is_repeatable() {
+        let a = play(&mut RandomBot::new(3), 100);
+        let b = play(&mut RandomBot::new(3), 100);
+        assert_eq!(a.snake().body_positions(), b.snake().body_positions());
+        assert_eq!(a.tick(), b.tick());
+    }
+}
@@ -0,0 +1,407 @@
+use std::fmt;
+use std::net::ToSocketAddrs;
+use std::path::PathBuf;
+
+use snake::bots;
+use snake::difficulty::{Difficulty, SpeedCurve};
+use snake::level;
+use snake::world::BoardMode;
+
+pub const USAGE: &str = "\
+usage: snake [options]
+
+board:
+  --width N               board width in cells, walls included (default 15)
+  --height N              board height in cells, walls included (default 15)
+  --level PATH            play a level file, which sets the board size
+  --mode walls|wrap       what happens at the edge of the board
+game:
+  --seed N                seed for food placement (random by default)
+  --difficulty NAME       easy, normal, hard or insane
+  --move-period SECONDS   fixed time per move, instead of speeding up
+  --speed-curve S,M,P,F   start and minimum period, points per step, factor
+  --players N             local match between N snakes
+  --bots LIST             keyboard or a bot name per player, e.g. keyboard,greedy;
+                          hamilton only works with an even --width or --height and
+                          no walls, e.g. --width 16, and plays like autopilot on the
+                          default 15x15 board
+  --interpolate           draw the snake gliding between cells
+  --no-interpolate        move the snake cell by cell, whatever the config file says
+files:
+  --highscore PATH        where high scores are kept (default highscore.txt)
+  --resume                continue the saved game
+  --record PATH           record games to a replay file
+  --replay PATH           watch a replay
+front end:
+  --mute                  play without sound
+  --no-mute               play with sound, whatever the config file says
+  --tui                   play in the terminal instead of a window
+  --spectator-ws ADDR     WebSocket bind address (default 0.0.0.0:9001)
+  --spectator-http ADDR   spectator page bind address (default 0.0.0.0:8000)
+  --config PATH           read settings from this file instead of the default one
+  -h, --help              show this help";
+
+/// What was asked for on the command line. Settings that are not given are
+/// left as `None` for the game's defaults.
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub level: Option<PathBuf>,
+    pub mode: Option<BoardMode>,
+    pub seed: Option<u64>,
+    pub difficulty: Option<Difficulty>,
+    pub speed_curve: Option<SpeedCurve>,
+    pub players: Option<usize>,
+    /// One entry per player, `keyboard` or a name from [`bots::NAMES`].
+    pub bots: Option<Vec<String>>,
+    pub interpolate: Option<bool>,
+    pub high_score_path: Option<PathBuf>,
+    pub resume: bool,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub mute: Option<bool>,
+    pub tui: bool,
+    pub spectator_ws: Option<String>,
+    pub spectator_http: Option<String>,
+    /// A config file to read instead of the one in the config directory.
+    pub config: Option<PathBuf>,
+    pub help: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    UnknownArgument(String),
+    MissingValue(String),
+    Invalid {
+        flag: String,
+        value: String,
+        expected: String,
+    },
+    Conflict(&'static str, &'static str),
+    /// A flag for something this build was compiled without.
+    NeedsFeature(&'static str, &'static str),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownArgument(arg) => write!(f, "unknown argument {:?}", arg),
+            CliError::MissingValue(flag) => write!(f, "{} needs a value", flag),
+            CliError::Invalid {
+                flag,
+                value,
+                expected,
+            } => write!(f, "invalid {} {:?}, expected {}", flag, value, expected),
+            CliError::Conflict(first, second) => {
+                write!(f, "{} and {} cannot be used together", first, second)
+            }
+            CliError::NeedsFeature(flag, feature) => {
+                write!(f, "{} needs a build with `--features {}`", flag, feature)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl Options {
+    /// Reads the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, CliError> {
+        let mut options = Options::default();
+        let mut move_period = None;
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let flag = flag.as_str();
+            match flag {
+                "-h" | "--help" => options.help = true,
+                "--interpolate" => options.interpolate = Some(true),
+                "--no-interpolate" => options.interpolate = Some(false),
+                "--resume" => options.resume = true,
+                "--mute" => options.mute = Some(true),
+                "--no-mute" => options.mute = Some(false),
+                "--tui" => options.tui = true,
+                "--config" => options.config = Some(value(&mut args, flag)?.into()),
+                "--width" => {
+                    let text = value(&mut args, flag)?;
+                    options.width = Some(check(flag, text, |text| board_side(text, level::MIN_WIDTH))?);
+                }
+                "--height" => {
+                    let text = value(&mut args, flag)?;
+                    options.height = Some(check(flag, text, |text| board_side(text, level::MIN_HEIGHT))?);
+                }
+                "--level" => options.level = Some(value(&mut args, flag)?.into()),
+                "--mode" => options.mode = Some(check(flag, value(&mut args, flag)?, mode)?),
+                "--seed" => {
+                    let text = value(&mut args, flag)?;
+                    options.seed = Some(check(flag, text, |text| {
+                        text.parse().map_err(|_| "a whole number".to_string())
+                    })?);
+                }
+                "--difficulty" => {
+                    options.difficulty = Some(check(flag, value(&mut args, flag)?, difficulty)?)
+                }
+                "--move-period" => {
+                    move_period = Some(check(flag, value(&mut args, flag)?, self::move_period)?)
+                }
+                "--speed-curve" => {
+                    options.speed_curve = Some(check(flag, value(&mut args, flag)?, speed_curve)?)
+                }
+                "--players" => options.players = Some(check(flag, value(&mut args, flag)?, players)?),
+                "--bots" => options.bots = Some(check(flag, value(&mut args, flag)?, bot_names)?),
+                "--highscore" => options.high_score_path = Some(value(&mut args, flag)?.into()),
+                "--record" => options.record = Some(value(&mut args, flag)?.into()),
+                "--replay" => options.replay = Some(value(&mut args, flag)?.into()),
+                "--spectator-ws" => {
+                    options.spectator_ws = Some(check(flag, value(&mut args, flag)?, address)?)
+                }
+                "--spectator-http" => {
+                    options.spectator_http = Some(check(flag, value(&mut args, flag)?, address)?)
+                }
+                _ => return Err(CliError::UnknownArgument(flag.to_string())),
+            }
+        }
+
+        if options.level.is_some() {
+            if options.width.is_some() {
+                return Err(CliError::Conflict("--level", "--width"));
+            }
+            if options.height.is_some() {
+                return Err(CliError::Conflict("--level", "--height"));
+            }
+        }
+        if let Some(period) = move_period {
+            if options.speed_curve.is_some() {
+                return Err(CliError::Conflict("--move-period", "--speed-curve"));
+            }
+            options.speed_curve = Some(SpeedCurve::constant(period));
+        }
+        if let (Some(players), Some(bots)) = (options.players, &options.bots) {
+            if bots.len() > players {
+                return Err(invalid("--bots", bots.join(","), "no more entries than --players".to_string()));
+            }
+        }
+        if !cfg!(feature = "spectator") {
+            if options.spectator_ws.is_some() {
+                return Err(CliError::NeedsFeature("--spectator-ws", "spectator"));
+            }
+            if options.spectator_http.is_some() {
+                return Err(CliError::NeedsFeature("--spectator-http", "spectator"));
+            }
+        }
+        Ok(options)
+    }
+
+    /// Fills in everything not given here from `fallback`, e.g. the config
+    /// file. The board size and level go together: setting either here
+    /// leaves all of the fallback's board aside.
+    pub fn or(self, fallback: Options) -> Options {
+        let own_board = self.width.is_some() || self.height.is_some() || self.level.is_some();
+        let (width, height, level) = if own_board {
+            (self.width, self.height, self.level)
+        } else {
+            (fallback.width, fallback.height, fallback.level)
+        };
+        Options {
+            width,
+            height,
+            level,
+            mode: self.mode.or(fallback.mode),
+            seed: self.seed.or(fallback.seed),
+            difficulty: self.difficulty.or(fallback.difficulty),
+            speed_curve: self.speed_curve.or(fallback.speed_curve),
+            players: self.players.or(fallback.players),
+            bots: self.bots.or(fallback.bots),
+            interpolate: self.interpolate.or(fallback.interpolate),
+            high_score_path: self.high_score_path.or(fallback.high_score_path),
+            resume: self.resume || fallback.resume,
+            record: self.record.or(fallback.record),
+            replay: self.replay.or(fallback.replay),
+            mute: self.mute.or(fallback.mute),
+            tui: self.tui || fallback.tui,
+            spectator_ws: self.spectator_ws.or(fallback.spectator_ws),
+            spectator_http: self.spectator_http.or(fallback.spectator_http),
+            config: self.config.or(fallback.config),
+            help: self.help || fallback.help,
+        }
+    }
+}
+
+/// The argument following `flag`, e.g. `42` in `--seed 42`.
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, CliError> {
+    args.next().ok_or_else(|| CliError::MissingValue(flag.to_string()))
+}
+
+/// Runs one of the setting parsers below on `flag`'s value.
+fn check<T>(
+    flag: &str,
+    text: String,
+    parse: impl FnOnce(&str) -> Result<T, String>,
+) -> Result<T, CliError> {
+    parse(&text).map_err(|expected| invalid(flag, text, expected))
+}
+
+fn invalid(flag: &str, value: String, expected: String) -> CliError {
+    CliError::Invalid {
+        flag: flag.to_string(),
+        value,
+        expected,
+    }
+}
+
+// Parsers for settings that can come from the command line or the config
+// file. On failure they describe what was expected instead.
+
+pub fn board_side(text: &str, min: i32) -> Result<i32, String> {
+    match text.parse() {
+        Ok(side) if (min..=level::MAX_SIDE).contains(&side) => Ok(side),
+        _ => Err(format!("a number of cells from {} to {}", min, level::MAX_SIDE)),
+    }
+}
+
+pub fn mode(text: &str) -> Result<BoardMode, String> {
+    BoardMode::from_name(text).ok_or_else(|| "walls or wrap".to_string())
+}
+
+pub fn difficulty(text: &str) -> Result<Difficulty, String> {
+    Difficulty::from_name(text).ok_or_else(|| "easy, normal, hard or insane".to_string())
+}
+
+pub fn players(text: &str) -> Result<usize, String> {
+    match text.parse() {
+        Ok(players) if players >= 1 => Ok(players),
+        _ => Err("a count of at least 1".to_string()),
+    }
+}
+
+pub fn move_period(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(period) if period > 0.0 && period.is_finite() => Ok(period),
+        _ => Err("seconds above 0".to_string()),
+    }
+}
+
+pub fn speed_curve(text: &str) -> Result<SpeedCurve, String> {
+    SpeedCurve::parse(text).ok_or_else(|| "start,min,points,factor".to_string())
+}
+
+pub fn bot_names(text: &str) -> Result<Vec<String>, String> {
+    let names: Vec<String> = text.split(',').map(|name| name.trim().to_string()).collect();
+    let known = |name: &String| name == "keyboard" || bots::NAMES.contains(&name.as_str());
+    if names.iter().all(known) {
+        Ok(names)
+    } else {
+        Err(format!("keyboard or one of {} per player", bots::NAMES.join(", ")))
+    }
+}
+
+/// A bind address such as `0.0.0.0:9001` or `localhost:9001`.
+pub fn address(text: &str) -> Result<String, String> {
+    match port(text) {
+        Some(_) => Ok(text.to_string()),
+        None => Err("a host and port such as 0.0.0.0:9001".to_string()),
+    }
+}
+
+/// The port a bind address listens on.
+pub fn port(address: &str) -> Option<u16> {
+    address.to_socket_addrs().ok()?.next().map(|addr| addr.port())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, CliError> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_keep_the_defaults() {
+        assert_eq!(parse(&[]), Ok(Options::default()));
+    }
+
+    #[test]
+    fn reads_settings() {
+        let options = parse(&[
+            "--width", "20", "--height", "12", "--seed", "42", "--mode", "wrap", "--mute",
+            "--bots", "keyboard,greedy", "--highscore", "scores.txt",
+        ])
+        .unwrap();
+        assert_eq!(options.width, Some(20));
+        assert_eq!(options.height, Some(12));
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.mode, Some(BoardMode::Wrap));
+        assert_eq!(options.mute, Some(true));
+        assert_eq!(options.bots, Some(vec!["keyboard".to_string(), "greedy".to_string()]));
+        assert_eq!(options.high_score_path, Some(PathBuf::from("scores.txt")));
+    }
+
+    #[test]
+    fn move_period_is_a_constant_speed() {
+        let options = parse(&["--move-period", "0.2"]).unwrap();
+        assert_eq!(options.speed_curve, Some(SpeedCurve::constant(0.2)));
+        assert!(parse(&["--move-period", "0"]).is_err());
+        assert_eq!(
+            parse(&["--move-period", "0.2", "--speed-curve", "0.3,0.1,50,0.9"]),
+            Err(CliError::Conflict("--move-period", "--speed-curve"))
+        );
+    }
+
+    #[test]
+    fn rejects_boards_too_small_to_spawn_on() {
+        let err = parse(&["--width", "2", "--height", "2"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid --width \"2\", expected a number of cells from 6 to 100"
+        );
+        assert!(parse(&["--height", "3"]).is_err());
+        assert!(parse(&["--width", "101"]).is_err());
+        assert!(parse(&["--width", "6", "--height", "4"]).is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert!(parse(&["--seed", "-1"]).is_err());
+        assert!(parse(&["--mode", "torus"]).is_err());
+        assert!(parse(&["--difficulty", "brutal"]).is_err());
+        assert!(parse(&["--players", "0"]).is_err());
+        assert!(parse(&["--bots", "keyboard,clever"]).is_err());
+        assert!(parse(&["--spectator-ws", "nowhere"]).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_and_incomplete_arguments() {
+        assert_eq!(parse(&["--fast"]), Err(CliError::UnknownArgument("--fast".to_string())));
+        assert_eq!(parse(&["--seed"]), Err(CliError::MissingValue("--seed".to_string())));
+    }
+
+    #[test]
+    fn given_options_win_over_the_fallback() {
+        let given = parse(&["--seed", "1", "--width", "20"]).unwrap();
+        let fallback = parse(&["--seed", "2", "--mode", "wrap", "--level", "levels/pillars.txt"]).unwrap();
+        let merged = given.or(fallback);
+        assert_eq!(merged.seed, Some(1));
+        assert_eq!(merged.mode, Some(BoardMode::Wrap));
+        assert_eq!(merged.width, Some(20));
+        assert_eq!(merged.level, None);
+    }
+
+    #[test]
+    fn switches_can_be_turned_off_over_the_fallback() {
+        let given = parse(&["--no-mute", "--no-interpolate"]).unwrap();
+        let fallback = parse(&["--mute", "--interpolate"]).unwrap();
+        let merged = given.or(fallback);
+        assert_eq!(merged.mute, Some(false));
+        assert_eq!(merged.interpolate, Some(false));
+        assert_eq!(parse(&[]).unwrap().or(parse(&["--mute"]).unwrap()).mute, Some(true));
+    }
+
+    #[test]
+    fn level_sets_the_board_size() {
+        assert_eq!(
+            parse(&["--level", "levels/pillars.txt", "--width", "20"]),
+            Err(CliError::Conflict("--level", "--width"))
+        );
+    }
+}
@@ -0,0 +1,562 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use piston_window as pw;
+use toml::Spanned;
+use toml_edit::DocumentMut;
+use snake::difficulty::SpeedCurve;
+use snake::level;
+
+use crate::cli::{self, Options};
+use crate::game::Palette;
+use crate::gamepad;
+use crate::keys::{self, Action, KeyMap};
+
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Settings read from the config file. Anything the file leaves out keeps
+/// its default, and the command line overrides the file.
+///
+/// The file is TOML, with every setting in a table:
+///
+/// ```toml
+/// [board]
+/// width = 20
+/// height = 15
+/// mode = "wrap"
+/// players = 2
+///
+/// [speed]
+/// difficulty = "hard"
+///
+/// [colors]
+/// snake = "#2080ff"
+///
+/// [keys]
+/// up = ["Up", "Z"]
+/// pause = ["P"]
+///
+/// [audio]
+/// volume = 0.5
+///
+/// [gamepad]
+/// deadzone = 0.3
+///
+/// [spectator]
+/// ws = "127.0.0.1:9001"
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    /// The settings that can also be given on the command line.
+    pub options: Options,
+    pub palette: Palette,
+    pub keys: KeyMap,
+    /// Sound volume from 0.0 to 1.0.
+    pub volume: f32,
+    /// How far a stick leans before it turns, from 0.0 to 1.0.
+    pub deadzone: f64,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            options: Options::default(),
+            palette: Palette::default(),
+            keys: KeyMap::default(),
+            volume: 1.0,
+            deadzone: gamepad::DEFAULT_DEADZONE,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Syntax { line: usize, message: String },
+    UnknownKey { line: usize, key: String },
+    Invalid {
+        line: usize,
+        key: String,
+        value: String,
+        expected: String,
+    },
+    Conflict { line: usize, key: String, other: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "io error: {}", err),
+            ConfigError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            ConfigError::UnknownKey { line, key } => write!(f, "line {}: unknown setting {}", line, key),
+            ConfigError::Invalid {
+                line,
+                key,
+                value,
+                expected,
+            } => write!(f, "line {}: invalid {} {}, expected {}", line, key, value, expected),
+            ConfigError::Conflict { line, key, other } => {
+                write!(f, "line {}: {} cannot be used together with {}", line, key, other)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(err: std::io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+/// Where the config file lives when no path is given:
+/// `$XDG_CONFIG_HOME/snake/config.toml`, falling back to `~/.config` and
+/// then `%APPDATA%` on Windows.
+pub fn default_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(dir.join("snake").join(CONFIG_FILE))
+}
+
+pub fn load_from(path: &Path) -> Result<Config, ConfigError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// The tables of a config file and the settings in each, with where they
+/// are in the text.
+type Tables = BTreeMap<String, BTreeMap<String, Spanned<toml::Value>>>;
+
+pub fn parse(text: &str) -> Result<Config, ConfigError> {
+    let tables: Tables = toml::from_str(text).map_err(|e| ConfigError::Syntax {
+        line: line_at(text, e.span().map_or(0, |span| span.start)),
+        message: e.message().to_string(),
+    })?;
+    // In file order, so the first bad setting is the one reported.
+    let mut settings: Vec<(String, &Spanned<toml::Value>)> = tables
+        .iter()
+        .flat_map(|(table, values)| values.iter().map(move |(name, value)| (format!("{}.{}", table, name), value)))
+        .collect();
+    settings.sort_by_key(|(_, value)| value.span().start);
+
+    let mut config = Config::default();
+    // Lines of settings that exclude each other, for the error message.
+    let mut board_size_line = None;
+    let mut level_line = None;
+    let mut move_period_line = None;
+    let mut speed_curve_line = None;
+
+    for (key, value) in &settings {
+        let line = line_at(text, value.span().start);
+        let setting = Setting {
+            line,
+            key,
+            value: value.get_ref(),
+        };
+
+        let options = &mut config.options;
+        match key.as_str() {
+            "board.width" => {
+                options.width = Some(setting.written(|text| cli::board_side(text, level::MIN_WIDTH))?);
+                board_size_line = Some(line);
+            }
+            "board.height" => {
+                options.height = Some(setting.written(|text| cli::board_side(text, level::MIN_HEIGHT))?);
+                board_size_line = Some(line);
+            }
+            "board.level" => {
+                options.level = Some(setting.string(|text| Ok(text.into()))?);
+                level_line = Some(line);
+            }
+            "board.mode" => options.mode = Some(setting.string(cli::mode)?),
+            "board.players" => options.players = Some(setting.written(cli::players)?),
+            "speed.difficulty" => options.difficulty = Some(setting.string(cli::difficulty)?),
+            "speed.move_period" => {
+                options.speed_curve = Some(SpeedCurve::constant(setting.written(cli::move_period)?));
+                move_period_line = Some(line);
+            }
+            "speed.speed_curve" => {
+                options.speed_curve = Some(setting.string(cli::speed_curve)?);
+                speed_curve_line = Some(line);
+            }
+            "speed.interpolate" => options.interpolate = Some(setting.written(boolean)?),
+            "audio.volume" => config.volume = setting.written(volume)?,
+            "audio.mute" => options.mute = Some(setting.written(boolean)?),
+            "gamepad.deadzone" => config.deadzone = setting.written(deadzone)?,
+            "spectator.ws" => options.spectator_ws = Some(setting.string(cli::address)?),
+            "spectator.http" => options.spectator_http = Some(setting.string(cli::address)?),
+            _ => {
+                if let Some(action) = key.strip_prefix("keys.").and_then(Action::from_name) {
+                    config.keys.set(action, setting.check(key_list(setting.value))?);
+                } else if let Some(slot) = key.strip_prefix("colors.").and_then(|name| color_slot(&mut config.palette, name)) {
+                    *slot = setting.string(color)?;
+                } else {
+                    return Err(ConfigError::UnknownKey { line, key: key.clone() });
+                }
+            }
+        }
+    }
+
+    // The error points at whichever of the two came last.
+    if let (Some(level), Some(size)) = (level_line, board_size_line) {
+        return Err(if level > size {
+            conflict(level, "board.level", "board.width and board.height")
+        } else {
+            conflict(size, "board.width and board.height", "board.level")
+        });
+    }
+    if let (Some(period), Some(curve)) = (move_period_line, speed_curve_line) {
+        return Err(if period > curve {
+            conflict(period, "speed.move_period", "speed.speed_curve")
+        } else {
+            conflict(curve, "speed.speed_curve", "speed.move_period")
+        });
+    }
+    Ok(config)
+}
+
+/// Writes the bindings that differ from the defaults as the `[keys]` table
+/// of the config file at `path`, keeping the rest of the file as it is. The
+/// file and its directory are created if missing.
+pub fn save_key_map(path: &Path, map: &KeyMap) -> Result<(), ConfigError> {
+    rewrite(path, |document| set_key_map(document, map))
+}
+
+/// Writes `settings` to the config file at `path`, each a `table.key` with
+/// its new value or `None` to remove it. Everything else, comments
+/// included, is kept as it is, and the file and its directory are created
+/// if missing.
+pub fn save_settings(path: &Path, settings: &[(&str, Option<toml_edit::Value>)]) -> Result<(), ConfigError> {
+    rewrite(path, |document| set_settings(document, settings))
+}
+
+fn rewrite(path: &Path, edit: impl FnOnce(&mut DocumentMut)) -> Result<(), ConfigError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    // A file that does not parse is left alone rather than overwritten.
+    let mut document: DocumentMut = text.parse().map_err(|e: toml_edit::TomlError| ConfigError::Syntax {
+        line: line_at(&text, e.span().map_or(0, |span| span.start)),
+        message: e.message().to_string(),
+    })?;
+    edit(&mut document);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, document.to_string())?;
+    Ok(())
+}
+
+/// Changes `settings` in place where they already are, keeping any comment
+/// after the value, and adds them to their table (or a new one) where they
+/// are not.
+fn set_settings(document: &mut DocumentMut, settings: &[(&str, Option<toml_edit::Value>)]) {
+    for (key, value) in settings {
+        let (table, name) = key.split_once('.').expect("settings are named table.key");
+        match value {
+            Some(value) => {
+                let mut value = value.clone();
+                let table = document.entry(table).or_insert_with(toml_edit::table);
+                let Some(table) = table.as_table_like_mut() else {
+                    continue;
+                };
+                if let Some(old) = table.get(name).and_then(toml_edit::Item::as_value) {
+                    *value.decor_mut() = old.decor().clone();
+                }
+                table.insert(name, toml_edit::Item::Value(value));
+            }
+            None => {
+                if let Some(table) = document.get_mut(table).and_then(|table| table.as_table_like_mut()) {
+                    table.remove(name);
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the `[keys]` table with the bindings of `map` that differ from
+/// the defaults, or drops it when none do.
+fn set_key_map(document: &mut DocumentMut, map: &KeyMap) {
+    let defaults = KeyMap::default();
+    let mut table = toml_edit::Table::new();
+    for action in Action::ALL {
+        if map.keys(action) != defaults.keys(action) {
+            let names: toml_edit::Array = map.keys(action).iter().map(|key| keys::key_name(*key)).collect();
+            table.insert(action.name(), toml_edit::value(names));
+        }
+    }
+    if table.is_empty() {
+        document.remove("keys");
+    } else {
+        document.insert("keys", toml_edit::Item::Table(table));
+    }
+}
+
+/// The line `offset` bytes into `text` is on, counting from 1.
+fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// One `key = value` line, for error messages.
+struct Setting<'a> {
+    line: usize,
+    key: &'a str,
+    value: &'a toml::Value,
+}
+
+impl Setting<'_> {
+    /// Turns what a parser expected into an error pointing at this line.
+    fn check<T>(&self, parsed: Result<T, String>) -> Result<T, ConfigError> {
+        parsed.map_err(|expected| ConfigError::Invalid {
+            line: self.line,
+            key: self.key.to_string(),
+            value: self.value.to_string(),
+            expected,
+        })
+    }
+
+    /// Runs `parse` on the contents of a string value.
+    fn string<T>(&self, parse: impl FnOnce(&str) -> Result<T, String>) -> Result<T, ConfigError> {
+        match self.value.as_str() {
+            Some(text) => self.check(parse(text)),
+            None => self.check(Err("text in quotes".to_string())),
+        }
+    }
+
+    /// Runs `parse` on the value as written, such as `20`, `0.5` or `true`.
+    /// Quoted values keep their quotes, so a number parser turns them down.
+    fn written<T>(&self, parse: impl FnOnce(&str) -> Result<T, String>) -> Result<T, ConfigError> {
+        self.check(parse(&self.value.to_string()))
+    }
+}
+
+fn conflict(line: usize, key: &str, other: &str) -> ConfigError {
+    ConfigError::Conflict {
+        line,
+        key: key.to_string(),
+        other: other.to_string(),
+    }
+}
+
+/// The palette entry a `[colors]` key sets.
+fn color_slot<'a>(palette: &'a mut Palette, name: &str) -> Option<&'a mut pw::graphics::types::Color> {
+    let slot = match name {
+        "background" => &mut palette.background,
+        "snake" | "player1" => &mut palette.snakes[0],
+        "player2" => &mut palette.snakes[1],
+        "player3" => &mut palette.snakes[2],
+        "player4" => &mut palette.snakes[3],
+        "food" => &mut palette.food,
+        "bonus_food" => &mut palette.bonus_food,
+        "shrink_food" => &mut palette.shrink_food,
+        "speed_up_food" => &mut palette.speed_up_food,
+        "slow_down_food" => &mut palette.slow_down_food,
+        "border" => &mut palette.border,
+        "wrap_border" => &mut palette.wrap_border,
+        "text" => &mut palette.text,
+        _ => return None,
+    };
+    Some(slot)
+}
+
+/// `#rrggbb`, or `#rrggbbaa` with opacity.
+fn color(text: &str) -> Result<pw::graphics::types::Color, String> {
+    let expected = || "a colour such as #00cc00 or #00cc0080".to_string();
+    let hex = text.strip_prefix('#').filter(|hex| matches!(hex.len(), 6 | 8)).ok_or_else(expected)?;
+    let mut channels = [1.0; 4];
+    for (channel, index) in channels.iter_mut().zip((0..hex.len()).step_by(2)) {
+        let byte = hex
+            .get(index..index + 2)
+            .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            .ok_or_else(expected)?;
+        *channel = byte as f32 / 255.0;
+    }
+    Ok(channels)
+}
+
+/// A list of key names. An empty list leaves the action unbound.
+fn key_list(value: &toml::Value) -> Result<Vec<pw::Key>, String> {
+    value
+        .as_array()
+        .and_then(|names| {
+            names
+                .iter()
+                .map(|name| name.as_str().and_then(keys::key_from_name))
+                .collect::<Option<Vec<pw::Key>>>()
+        })
+        .ok_or_else(|| "a list of key names, such as [\"Up\", \"W\"]".to_string())
+}
+
+fn boolean(text: &str) -> Result<bool, String> {
+    match text {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err("true or false".to_string()),
+    }
+}
+
+fn volume(text: &str) -> Result<f32, String> {
+    match text.parse::<f32>() {
+        Ok(volume) if (0.0..=1.0).contains(&volume) => Ok(volume),
+        _ => Err("a number from 0.0 to 1.0".to_string()),
+    }
+}
+
+fn deadzone(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(deadzone) if (0.0..1.0).contains(&deadzone) => Ok(deadzone),
+        _ => Err("a number from 0.0 up to but not including 1.0".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use snake::difficulty::Difficulty;
+    use snake::world::BoardMode;
+
+    #[test]
+    fn empty_file_keeps_the_defaults() {
+        assert_eq!(parse("# nothing here\n").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn reads_every_section() {
+        let config = parse(
+            "[board]\nwidth = 20\nheight = 12\nmode = \"wrap\" # around the edges\n\
+             [speed]\ndifficulty = \"hard\"\nmove_period = 0.2\n\
+             [colors]\nsnake = \"#2080ff\"\nfood = \"#ff000080\"\n\
+             [keys]\nup = [\"Up\", \"Z\"]\nrestart = []\n\
+             [audio]\nvolume = 0.5\nmute = true\n\
+             [gamepad]\ndeadzone = 0.25\n\
+             [spectator]\nws = \"127.0.0.1:9100\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.options.width, Some(20));
+        assert_eq!(config.options.height, Some(12));
+        assert_eq!(config.options.mode, Some(BoardMode::Wrap));
+        assert_eq!(config.options.difficulty, Some(Difficulty::Hard));
+        assert_eq!(config.options.speed_curve, Some(SpeedCurve::constant(0.2)));
+        assert_eq!(config.palette.snakes[0], [32.0 / 255.0, 128.0 / 255.0, 1.0, 1.0]);
+        assert_eq!(config.palette.food, [1.0, 0.0, 0.0, 128.0 / 255.0]);
+        assert_eq!(config.keys.keys(Action::TurnUp), &[pw::Key::Up, pw::Key::Z]);
+        assert_eq!(config.keys.keys(Action::Restart), &[]);
+        assert_eq!(config.keys.keys(Action::TurnDown), KeyMap::default().keys(Action::TurnDown));
+        assert_eq!(config.volume, 0.5);
+        assert_eq!(config.deadzone, 0.25);
+        assert_eq!(config.options.mute, Some(true));
+        assert_eq!(config.options.spectator_ws, Some("127.0.0.1:9100".to_string()));
+    }
+
+    #[test]
+    fn errors_name_the_line_and_key() {
+        let err = parse("[board]\nwidth = 20\nheight = 2\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3: invalid board.height 2, expected a number of cells from 4 to 100"
+        );
+        let err = parse("[colors]\nsnake = \"green\"\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: invalid colors.snake \"green\", expected a colour such as #00cc00 or #00cc0080"
+        );
+        let err = parse("[audio]\nvolume = 0.5\nloudness = 3\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 3: unknown setting audio.loudness");
+        assert!(parse("[keys]\nup = [\"Up\", \"Jump\"]\n").is_err());
+        assert!(matches!(parse("[keys]\njump = [\"J\"]\n"), Err(ConfigError::UnknownKey { line: 2, .. })));
+    }
+
+    #[test]
+    fn values_need_the_right_type() {
+        let err = parse("[board]\nwidth = \"20\"\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: invalid board.width \"20\", expected a number of cells from 6 to 100"
+        );
+        let err = parse("[board]\nmode = 1\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: invalid board.mode 1, expected text in quotes");
+        assert!(matches!(parse("[keys]\nup = \"Up\"\n"), Err(ConfigError::Invalid { line: 2, .. })));
+        assert!(matches!(parse("[audio]\nmute = \"yes\"\n"), Err(ConfigError::Invalid { line: 2, .. })));
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(matches!(parse("width = 20\n"), Err(ConfigError::Syntax { line: 1, .. })));
+        assert!(matches!(parse("[board]\nwidth 20\n"), Err(ConfigError::Syntax { line: 2, .. })));
+        assert!(matches!(parse("[board]\nmode = wrap\n"), Err(ConfigError::Syntax { line: 2, .. })));
+    }
+
+    #[test]
+    fn rejects_settings_that_exclude_each_other() {
+        let err = parse("[board]\nlevel = \"levels/pillars.txt\"\nwidth = 20\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3: board.width and board.height cannot be used together with board.level"
+        );
+        let err = parse("[speed]\nmove_period = 0.2\nspeed_curve = \"0.3,0.1,50,0.9\"\n").unwrap_err();
+        assert!(matches!(err, ConfigError::Conflict { line: 3, .. }));
+    }
+
+    fn edited(text: &str, edit: impl FnOnce(&mut DocumentMut)) -> String {
+        let mut document: DocumentMut = text.parse().unwrap();
+        edit(&mut document);
+        document.to_string()
+    }
+
+    #[test]
+    fn saved_keys_replace_only_the_keys_table() {
+        let mut map = KeyMap::default();
+        map.set(Action::Pause, vec![pw::Key::P, pw::Key::Return]);
+        let text = "[board]\nwidth = 20\n\n[keys]\nup = [\"Z\"]\n\n[audio]\nmute = true\n";
+        let text = edited(text, |document| set_key_map(document, &map));
+        assert_eq!(
+            text,
+            "[board]\nwidth = 20\n\n[keys]\npause = [\"P\", \"Enter\"]\n\n[audio]\nmute = true\n"
+        );
+        let config = parse(&text).unwrap();
+        assert_eq!(config.keys, map);
+        assert_eq!(config.options.mute, Some(true));
+        let text = edited(&text, |document| set_key_map(document, &KeyMap::default()));
+        assert_eq!(text, "[board]\nwidth = 20\n\n[audio]\nmute = true\n");
+    }
+
+    #[test]
+    fn saved_settings_change_values_in_place() {
+        let text = "# mine\n[board]\nlevel = \"levels/pillars.txt\"\nmode = \"walls\" # for now\n\n[audio]\nmute = true\n";
+        let text = edited(text, |document| {
+            set_settings(
+                document,
+                &[
+                    ("board.mode", Some("wrap".into())),
+                    ("board.width", Some(20.into())),
+                    ("board.level", None),
+                    ("speed.difficulty", Some("hard".into())),
+                ],
+            )
+        });
+        assert_eq!(
+            text,
+            "# mine\n[board]\nmode = \"wrap\" # for now\nwidth = 20\n\n[audio]\nmute = true\n\n[speed]\ndifficulty = \"hard\"\n"
+        );
+        let config = parse(&text).unwrap();
+        assert_eq!(config.options.mode, Some(BoardMode::Wrap));
+        assert_eq!(edited("", |document| set_settings(document, &[("audio.volume", Some(0.5.into()))])), "[audio]\nvolume = 0.5\n");
+    }
+
+    #[test]
+    fn command_line_overrides_the_file() {
+        let config = parse("[board]\nwidth = 20\nmode = \"wrap\"\n").unwrap();
+        let given = Options::parse(["--mode".to_string(), "walls".to_string()]).unwrap();
+        let options = given.or(config.options);
+        assert_eq!(options.mode, Some(BoardMode::Walls));
+        assert_eq!(options.width, Some(20));
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::food::Food;
+use crate::snake::{Direction, Snake};
+use crate::world::{BoardMode, Grid};
+
+/// How many presses can be buffered ahead of the snake. One is used per tick.
+pub const INPUT_QUEUE_LEN: usize = 3;
+
+/// Steers one snake: asked once per tick for the turn to take.
+///
+/// Keyboards, bots and remote players all sit behind this trait, so any
+/// of them can drive any snake in a [`World`](crate::world::World) or an
+/// [`Arena`](crate::arena::Arena).
+pub trait Controller {
+    /// A short name shown next to the snake it drives.
+    fn name(&self) -> &str;
+
+    /// Picks the turn for the coming tick, `None` to keep going straight.
+    fn next_turn(&mut self, view: &BoardView) -> Option<Direction>;
+
+    /// Forgets anything carried over from the previous game.
+    fn reset(&mut self) {}
+}
+
+/// A read-only look at the board from one snake's point of view.
+pub struct BoardView<'a> {
+    grid: &'a Grid,
+    mode: BoardMode,
+    walls: &'a HashSet<(i32, i32)>,
+    snake: &'a Snake,
+    rivals: Vec<&'a Snake>,
+    foods: &'a [Food],
+    tick: u64,
+}
+
+impl<'a> BoardView<'a> {
+    pub(crate) fn new(
+        grid: &'a Grid,
+        mode: BoardMode,
+        walls: &'a HashSet<(i32, i32)>,
+        snake: &'a Snake,
+        rivals: Vec<&'a Snake>,
+        foods: &'a [Food],
+        tick: u64,
+    ) -> BoardView<'a> {
+        BoardView {
+            grid,
+            mode,
+            walls,
+            snake,
+            rivals,
+            foods,
+            tick,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        self.grid
+    }
+
+    pub fn mode(&self) -> BoardMode {
+        self.mode
+    }
+
+    /// The snake being steered.
+    pub fn snake(&self) -> &Snake {
+        self.snake
+    }
+
+    /// Every other snake still on the board.
+    pub fn rivals(&self) -> &[&'a Snake] {
+        &self.rivals
+    }
+
+    pub fn foods(&self) -> &[Food] {
+        self.foods
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn is_wall(&self, x: i32, y: i32) -> bool {
+        self.walls.contains(&(x, y))
+    }
+
+    /// The cell one step from (x, y) in `direction`, wrapped around the
+    /// board in wrap mode.
+    pub fn neighbour(&self, (x, y): (i32, i32), direction: Direction) -> (i32, i32) {
+        let [dx, dy] = direction.as_array();
+        let next = (x + dx, y + dy);
+        match self.mode {
+            BoardMode::Walls => next,
+            BoardMode::Wrap => self.grid.wrap_bounds().wrap(next.0, next.1),
+        }
+    }
+
+    /// Whether a head could move onto (x, y) right now: inside the border,
+    /// off the walls and clear of every snake.
+    pub fn is_free(&self, x: i32, y: i32) -> bool {
+        self.grid.is_inside(x, y)
+            && !self.is_wall(x, y)
+            && !self.snake.contains(x, y)
+            && self.rivals.iter().all(|rival| !rival.contains(x, y))
+    }
+
+    /// The directions the snake can take on the coming tick without dying
+    /// straight away, straight ahead first.
+    pub fn safe_directions(&self) -> Vec<Direction> {
+        let heading = self.snake.head_direction();
+        let head = self.snake.head_position();
+        [heading, turn_left(heading), turn_right(heading)]
+            .into_iter()
+            .filter(|dir| {
+                let (x, y) = self.neighbour(head, *dir);
+                self.is_free(x, y)
+            })
+            .collect()
+    }
+}
+
+/// Turns `direction` a quarter to the left.
+pub fn turn_left(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Left,
+        Direction::Left => Direction::Down,
+        Direction::Down => Direction::Right,
+        Direction::Right => Direction::Up,
+    }
+}
+
+/// Turns `direction` a quarter to the right.
+pub fn turn_right(direction: Direction) -> Direction {
+    turn_left(direction).opposite()
+}
+
+/// Turns from a keyboard, gamepad or anything else that presses directions.
+///
+/// Presses are buffered, up to [`INPUT_QUEUE_LEN`] of them, and applied one
+/// per tick. Repeating or reversing the previous press, or the heading if
+/// none is buffered, is ignored, and so is a buffered turn that would
+/// reverse the snake by the time it comes up.
+#[derive(Default)]
+pub struct KeyboardController {
+    input: VecDeque<Direction>,
+}
+
+impl KeyboardController {
+    pub fn new() -> KeyboardController {
+        KeyboardController {
+            input: VecDeque::with_capacity(INPUT_QUEUE_LEN),
+        }
+    }
+
+    /// Buffers a press of `direction` while the snake moves towards `heading`.
+    pub fn input(&mut self, direction: Direction, heading: Direction) {
+        let last = self.input.back().copied().unwrap_or(heading);
+        if self.input.len() < INPUT_QUEUE_LEN && direction != last && direction != last.opposite() {
+            self.input.push_back(direction);
+        }
+    }
+}
+
+impl Controller for KeyboardController {
+    fn name(&self) -> &str {
+        "keyboard"
+    }
+
+    fn next_turn(&mut self, view: &BoardView) -> Option<Direction> {
+        let heading = view.snake().head_direction();
+        std::iter::from_fn(|| self.input.pop_front())
+            .find(|turn| *turn != heading && *turn != heading.opposite())
+    }
+
+    fn reset(&mut self) {
+        self.input.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::World;
+
+    #[test]
+    fn turning_left_four_times_comes_back() {
+        let mut direction = Direction::Up;
+        for _ in 0..4 {
+            direction = turn_left(direction);
+        }
+        assert_eq!(direction, Direction::Up);
+        assert_eq!(turn_right(Direction::Up), Direction::Right);
+    }
+
+    #[test]
+    fn view_reports_safe_directions() {
+        // The snake starts at (4, 2) heading right, so after one step up it is
+        // right below the border.
+        let mut world = World::new(15, 15, 0);
+        assert_eq!(
+            world.view().safe_directions(),
+            vec![Direction::Right, Direction::Up, Direction::Down]
+        );
+        world.start();
+        world.step_with(Some(Direction::Up));
+        assert_eq!(world.view().safe_directions(), vec![Direction::Left, Direction::Right]);
+    }
+
+    #[test]
+    fn wrap_mode_neighbours_wrap() {
+        let mut world = World::new(15, 15, 0);
+        world.set_mode(BoardMode::Wrap);
+        let view = world.view();
+        assert_eq!(view.neighbour((4, 1), Direction::Up), (4, 13));
+        assert!(view.safe_directions().contains(&Direction::Up));
+    }
+
+    #[test]
+    fn keyboard_applies_one_press_per_tick() {
+        let mut world = World::new(15, 15, 0);
+        world.start();
+        let mut keyboard = KeyboardController::new();
+        keyboard.input(Direction::Down, Direction::Right);
+        keyboard.input(Direction::Left, Direction::Right);
+        let turn = keyboard.next_turn(&world.view());
+        assert_eq!(turn, Some(Direction::Down));
+        world.step_with(turn);
+        assert_eq!(keyboard.next_turn(&world.view()), Some(Direction::Left));
+        assert_eq!(keyboard.next_turn(&world.view()), None);
+    }
+
+    #[test]
+    fn keyboard_skips_presses_that_would_reverse() {
+        // The snake heads right, so left would turn it back into itself.
+        let world = World::new(15, 15, 0);
+        let mut keyboard = KeyboardController::new();
+        keyboard.input(Direction::Left, Direction::Right);
+        keyboard.input(Direction::Up, Direction::Right);
+        keyboard.input(Direction::Up, Direction::Right);
+        assert_eq!(keyboard.next_turn(&world.view()), Some(Direction::Up));
+        assert_eq!(keyboard.next_turn(&world.view()), None);
+    }
+
+    #[test]
+    fn keyboard_ignores_the_heading_and_its_reverse() {
+        // With nothing buffered, a press is checked against the heading.
+        let mut keyboard = KeyboardController::new();
+        keyboard.input(Direction::Left, Direction::Right);
+        keyboard.input(Direction::Right, Direction::Right);
+        assert!(keyboard.input.is_empty());
+    }
+
+    #[test]
+    fn keyboard_checks_a_press_against_the_last_one() {
+        let mut keyboard = KeyboardController::new();
+        keyboard.input(Direction::Down, Direction::Right);
+        keyboard.input(Direction::Up, Direction::Right);
+        keyboard.input(Direction::Left, Direction::Right);
+        assert_eq!(keyboard.input, [Direction::Down, Direction::Left]);
+    }
+
+    #[test]
+    fn keyboard_buffers_a_few_presses_at_most() {
+        let mut keyboard = KeyboardController::new();
+        for direction in [Direction::Down, Direction::Right, Direction::Up, Direction::Left] {
+            keyboard.input(direction, Direction::Right);
+        }
+        assert_eq!(keyboard.input.len(), INPUT_QUEUE_LEN);
+    }
+
+    #[test]
+    fn reset_drops_buffered_presses() {
+        let world = World::new(15, 15, 0);
+        let mut keyboard = KeyboardController::new();
+        keyboard.input(Direction::Down, Direction::Right);
+        keyboard.reset();
+        assert_eq!(keyboard.next_turn(&world.view()), None);
+    }
+}
@@ -0,0 +1,203 @@
+/// How fast the snake starts out and how quickly it speeds up.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Insane => "insane",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL.into_iter().find(|difficulty| difficulty.name() == name)
+    }
+
+    /// The next preset, wrapping from insane back to easy.
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Insane,
+            Difficulty::Insane => Difficulty::Easy,
+        }
+    }
+
+    /// The preset before this one, wrapping from easy back to insane.
+    pub fn previous(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Insane,
+            Difficulty::Normal => Difficulty::Easy,
+            Difficulty::Hard => Difficulty::Normal,
+            Difficulty::Insane => Difficulty::Hard,
+        }
+    }
+
+    pub fn curve(&self) -> SpeedCurve {
+        match self {
+            Difficulty::Easy => SpeedCurve {
+                start_period: 0.4,
+                min_period: 0.2,
+                points_per_step: 100,
+                step_factor: 0.95,
+            },
+            Difficulty::Normal => SpeedCurve {
+                start_period: 0.3,
+                min_period: 0.12,
+                points_per_step: 50,
+                step_factor: 0.93,
+            },
+            Difficulty::Hard => SpeedCurve {
+                start_period: 0.2,
+                min_period: 0.08,
+                points_per_step: 40,
+                step_factor: 0.92,
+            },
+            Difficulty::Insane => SpeedCurve {
+                start_period: 0.12,
+                min_period: 0.05,
+                points_per_step: 30,
+                step_factor: 0.9,
+            },
+        }
+    }
+}
+
+/// Move period as a function of score: every `points_per_step` points the
+/// period is multiplied by `step_factor`, down to `min_period` seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpeedCurve {
+    pub start_period: f64,
+    pub min_period: f64,
+    pub points_per_step: u32,
+    pub step_factor: f64,
+}
+
+impl SpeedCurve {
+    /// A curve that never speeds up.
+    pub fn constant(period: f64) -> SpeedCurve {
+        SpeedCurve {
+            start_period: period,
+            min_period: period,
+            points_per_step: 1,
+            step_factor: 1.0,
+        }
+    }
+
+    /// Reads `start,min,points,factor`, e.g. `0.3,0.1,50,0.9`.
+    pub fn parse(text: &str) -> Option<SpeedCurve> {
+        let parts: Vec<&str> = text.split(',').map(str::trim).collect();
+        let [start, min, points, factor] = parts[..] else {
+            return None;
+        };
+        let curve = SpeedCurve {
+            start_period: start.parse().ok()?,
+            min_period: min.parse().ok()?,
+            points_per_step: points.parse().ok()?,
+            step_factor: factor.parse().ok()?,
+        };
+        let valid = curve.min_period > 0.0
+            && curve.start_period >= curve.min_period
+            && curve.points_per_step > 0
+            && curve.step_factor > 0.0
+            && curve.step_factor <= 1.0;
+        valid.then_some(curve)
+    }
+
+    /// Seconds per move at `points`.
+    pub fn period(&self, points: u32) -> f64 {
+        let steps = points / self.points_per_step.max(1);
+        let period = self.start_period * self.step_factor.powi(steps.min(i32::MAX as u32) as i32);
+        period.max(self.min_period)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names_roundtrip() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(Difficulty::from_name(difficulty.name()), Some(difficulty));
+        }
+        assert_eq!(Difficulty::from_name("nightmare"), None);
+    }
+
+    #[test]
+    fn next_cycles_through_all_presets() {
+        let mut difficulty = Difficulty::Easy;
+        for expected in Difficulty::ALL.iter().cycle().skip(1).take(4) {
+            difficulty = difficulty.next();
+            assert_eq!(difficulty, *expected);
+        }
+    }
+
+    #[test]
+    fn previous_undoes_next() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(difficulty.next().previous(), difficulty);
+        }
+    }
+
+    #[test]
+    fn harder_presets_start_faster() {
+        let periods: Vec<f64> = Difficulty::ALL.iter().map(|d| d.curve().period(0)).collect();
+        assert!(periods.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn period_shrinks_with_score_down_to_the_minimum() {
+        let curve = Difficulty::Normal.curve();
+        assert_eq!(curve.period(0), curve.start_period);
+        assert_eq!(curve.period(49), curve.start_period);
+        assert!(curve.period(50) < curve.start_period);
+        assert_eq!(curve.period(u32::MAX), curve.min_period);
+    }
+
+    #[test]
+    fn parse_reads_all_four_values() {
+        let curve = SpeedCurve::parse("0.3, 0.1, 50, 0.9").unwrap();
+        assert_eq!(
+            curve,
+            SpeedCurve {
+                start_period: 0.3,
+                min_period: 0.1,
+                points_per_step: 50,
+                step_factor: 0.9,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_rejects_bad_curves() {
+        assert_eq!(SpeedCurve::parse("0.3,0.1,50"), None);
+        assert_eq!(SpeedCurve::parse("0.3,0.1,0,0.9"), None);
+        assert_eq!(SpeedCurve::parse("0.1,0.3,50,0.9"), None);
+        assert_eq!(SpeedCurve::parse("0.3,0.1,50,1.5"), None);
+        assert_eq!(SpeedCurve::parse("fast,0.1,50,0.9"), None);
+    }
+
+    #[test]
+    fn constant_curve_never_changes() {
+        let curve = SpeedCurve::constant(0.25);
+        assert_eq!(curve.period(0), 0.25);
+        assert_eq!(curve.period(10_000), 0.25);
+    }
+}
@@ -42,6 +42,22 @@ pub fn draw_block(
     draw_rectangle(color, x, y, 1, 1, con, g);
 }
 
+/// Like [`draw_block`] but at fractional game coordinates, for interpolated movement.
+pub fn draw_block_at(
+    color: pw::graphics::types::Color,
+    x: f64,
+    y: f64,
+    con: &pw::graphics::Context,
+    g: &mut pw::wgpu_graphics::WgpuGraphics,
+) {
+    pw::graphics::rectangle(
+        color,
+        [x * BLOCK_SIZE, y * BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE],
+        con.transform,
+        g,
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
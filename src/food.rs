@@ -0,0 +1,137 @@
+/// The different foods that can appear on the board.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FoodKind {
+    /// Grows the snake by one segment.
+    Normal,
+    /// Grows the snake by several segments, but only lies around briefly.
+    Bonus,
+    /// Takes segments off the tail.
+    Shrink,
+    /// Makes the snake move faster.
+    SpeedUp,
+    /// Makes the snake move slower.
+    SlowDown,
+}
+
+impl FoodKind {
+    pub const ALL: [FoodKind; 5] = [
+        FoodKind::Normal,
+        FoodKind::Bonus,
+        FoodKind::Shrink,
+        FoodKind::SpeedUp,
+        FoodKind::SlowDown,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FoodKind::Normal => "normal",
+            FoodKind::Bonus => "bonus",
+            FoodKind::Shrink => "shrink",
+            FoodKind::SpeedUp => "speedup",
+            FoodKind::SlowDown => "slowdown",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FoodKind> {
+        FoodKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Relative chance of this kind being picked for a new food.
+    pub fn spawn_weight(&self) -> u32 {
+        match self {
+            FoodKind::Normal => 12,
+            FoodKind::Bonus => 3,
+            FoodKind::Shrink | FoodKind::SpeedUp | FoodKind::SlowDown => 2,
+        }
+    }
+
+    /// Ticks the food stays on the board, `None` if it never expires.
+    pub fn lifetime(&self) -> Option<u64> {
+        match self {
+            FoodKind::Normal => None,
+            FoodKind::Bonus => Some(25),
+            FoodKind::Shrink | FoodKind::SpeedUp | FoodKind::SlowDown => Some(60),
+        }
+    }
+
+    /// Points before the combo, time and speed bonuses of [`Score`](crate::score::Score).
+    pub fn points(&self) -> u32 {
+        match self {
+            FoodKind::Normal => 10,
+            FoodKind::Bonus => 50,
+            FoodKind::SpeedUp => 20,
+            FoodKind::Shrink | FoodKind::SlowDown => 5,
+        }
+    }
+
+    /// Segments the snake gains when eating it.
+    pub fn growth(&self) -> u32 {
+        match self {
+            FoodKind::Normal => 1,
+            FoodKind::Bonus => 3,
+            FoodKind::Shrink | FoodKind::SpeedUp | FoodKind::SlowDown => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Food {
+    kind: FoodKind,
+    x: i32,
+    y: i32,
+    expires_at: Option<u64>,
+}
+
+impl Food {
+    pub fn new(kind: FoodKind, x: i32, y: i32, expires_at: Option<u64>) -> Food {
+        Food {
+            kind,
+            x,
+            y,
+            expires_at,
+        }
+    }
+
+    pub fn kind(&self) -> FoodKind {
+        self.kind
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    /// The tick on which the food disappears if it has not been eaten.
+    pub fn expires_at(&self) -> Option<u64> {
+        self.expires_at
+    }
+
+    pub fn is_expired(&self, tick: u64) -> bool {
+        self.expires_at.is_some_and(|at| tick >= at)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn kind_names_roundtrip() {
+        for kind in FoodKind::ALL {
+            assert_eq!(FoodKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(FoodKind::from_name("cake"), None);
+    }
+
+    #[test]
+    fn normal_food_never_expires() {
+        let food = Food::new(FoodKind::Normal, 1, 1, None);
+        assert!(!food.is_expired(u64::MAX));
+    }
+
+    #[test]
+    fn food_expires_on_its_tick() {
+        let food = Food::new(FoodKind::Bonus, 1, 1, Some(10));
+        assert!(!food.is_expired(9));
+        assert!(food.is_expired(10));
+    }
+}
@@ -2,105 +2,181 @@ use piston_window as pw;
 use piston_window::Glyphs;
 use pw::graphics::Transformed;
 
-use rand::seq::IteratorRandom;
+use std::path::{Path, PathBuf};
+
+use snake::difficulty::{Difficulty, SpeedCurve};
+use snake::food::{Food, FoodKind};
+use snake::level::Level;
+use snake::persistence;
+use snake::replay::{self, Replay, ReplayPlayer};
+use snake::save::{self, SAVE_FILE};
+use snake::rng::GameRng;
+use snake::snake::{Direction, Snake};
+use snake::tick::TickScheduler;
+use snake::arena::{Arena, MatchResult};
+use snake::autopilot::Autopilot;
+use snake::controller::{Controller, KeyboardController};
+use snake::world::{BoardMode, GameState, Grid, StepOutcome, World};
 
 use crate::audio::SoundPlayer;
-use crate::draw::{draw_block, draw_rectangle, BLOCK_SIZE};
-use crate::snake::{Direction, Snake};
-use crate::persistence;
+use crate::config;
+use crate::keys::{key_name, Action, KeyMap};
+use crate::menu::{Item, Menu, Page};
+use crate::draw::{draw_block, draw_block_at, draw_rectangle, BLOCK_SIZE};
+#[cfg(feature = "debug_draw")]
+use crate::draw::to_coord;
 #[cfg(feature = "spectator")]
-use crate::spectator::GameSnapshot;
-
+use crate::spectator::{FoodSnapshot, GameSnapshot};
+
+const BACKGROUND_COLOR: pw::graphics::types::Color = [0.5, 0.5, 0.5, 1.0];
+const SNAKE_COLOR: pw::graphics::types::Color = [0.00, 0.80, 0.00, 1.0];
+/// Snake colours in a multi-snake match, by player.
+const PLAYER_COLORS: [pw::graphics::types::Color; 4] = [
+    SNAKE_COLOR,
+    [1.00, 0.50, 0.00, 1.0],
+    [0.90, 0.20, 0.70, 1.0],
+    [0.55, 0.35, 0.15, 1.0],
+];
 const FOOD_COLOR: pw::graphics::types::Color = [0.80, 0.00, 0.00, 1.0];
+const BONUS_FOOD_COLOR: pw::graphics::types::Color = [1.00, 0.80, 0.00, 1.0];
+const SHRINK_FOOD_COLOR: pw::graphics::types::Color = [0.60, 0.20, 0.80, 1.0];
+const SPEED_UP_FOOD_COLOR: pw::graphics::types::Color = [0.00, 0.80, 1.00, 1.0];
+const SLOW_DOWN_FOOD_COLOR: pw::graphics::types::Color = [0.20, 0.30, 0.90, 1.0];
 const BORDER_COLOR: pw::graphics::types::Color = [0.00, 0.00, 0.00, 1.0];
+const WRAP_BORDER_COLOR: pw::graphics::types::Color = [0.30, 0.30, 0.30, 1.0];
 const GAMEOVER_COLOR: pw::graphics::types::Color = [0.90, 0.00, 0.00, 0.5];
+const VICTORY_COLOR: pw::graphics::types::Color = [1.00, 0.80, 0.00, 0.5];
 const PAUSE_COLOR: pw::graphics::types::Color = [0.00, 0.00, 0.00, 0.5];
 const TEXT_COLOR: pw::graphics::types::Color = [1.0, 1.0, 1.0, 1.0];
 #[cfg(feature = "debug_draw")]
 const DEBUG_COLOR: pw::graphics::types::Color = [0.10, 0.80, 1.00, 0.9];
 const FONT_SIZE: u32 = 16;
 
-const MOVING_PERIOD: f64 = 0.3;
+/// Each speed level multiplies the moving period by this factor.
+const SPEED_FACTOR: f64 = 0.85;
 const RESTART_TIME: f64 = 3.0;
-
-#[derive(Clone, Debug)]
-enum GameState {
-    Menu,
-    Playing,
-    Paused,
-    GameOver { elapsed: f64, final_score: usize },
-}
-
-trait Renderable {
-    fn render(
-        &self,
-        con: &pw::graphics::Context,
-        g: &mut pw::wgpu_graphics::WgpuGraphics,
-        glyphs: &mut Glyphs,
-    );
+const MAX_PLAYBACK_SPEED: u32 = 8;
+/// Seconds a message stays in the HUD.
+const MESSAGE_TIME: f64 = 3.0;
+/// Actions listed at once on the rebind screen.
+const REBIND_ROWS: usize = 9;
+/// The board sizes the menu steps through, smallest first.
+const BOARD_SIZES: [(i32, i32); 4] = [(10, 10), (15, 15), (20, 15), (25, 20)];
+/// How much Left and Right change the volume in the menu.
+const VOLUME_STEP: f32 = 0.1;
+
+/// The colours the board is drawn in. Defaults to the classic look and can
+/// be changed from the config file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub background: pw::graphics::types::Color,
+    /// Snake colours by player; the first is also the single-player snake.
+    pub snakes: [pw::graphics::types::Color; 4],
+    pub food: pw::graphics::types::Color,
+    pub bonus_food: pw::graphics::types::Color,
+    pub shrink_food: pw::graphics::types::Color,
+    pub speed_up_food: pw::graphics::types::Color,
+    pub slow_down_food: pw::graphics::types::Color,
+    pub border: pw::graphics::types::Color,
+    pub wrap_border: pw::graphics::types::Color,
+    pub text: pw::graphics::types::Color,
 }
 
-struct Food {
-    exists: bool,
-    x: i32,
-    y: i32,
-}
-
-impl Food {
-    fn new(x: i32, y: i32) -> Food {
-        Food {
-            exists: true,
-            x,
-            y,
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette {
+            background: BACKGROUND_COLOR,
+            snakes: PLAYER_COLORS,
+            food: FOOD_COLOR,
+            bonus_food: BONUS_FOOD_COLOR,
+            shrink_food: SHRINK_FOOD_COLOR,
+            speed_up_food: SPEED_UP_FOOD_COLOR,
+            slow_down_food: SLOW_DOWN_FOOD_COLOR,
+            border: BORDER_COLOR,
+            wrap_border: WRAP_BORDER_COLOR,
+            text: TEXT_COLOR,
         }
     }
+}
 
-    fn draw(&self, con: &pw::graphics::Context, g: &mut pw::wgpu_graphics::WgpuGraphics) {
-        if self.exists {
-            draw_block(FOOD_COLOR, self.x, self.y, con, g);
+impl Palette {
+    pub fn food(&self, kind: FoodKind) -> pw::graphics::types::Color {
+        match kind {
+            FoodKind::Normal => self.food,
+            FoodKind::Bonus => self.bonus_food,
+            FoodKind::Shrink => self.shrink_food,
+            FoodKind::SpeedUp => self.speed_up_food,
+            FoodKind::SlowDown => self.slow_down_food,
         }
     }
 
-    fn position(&self) -> Option<(i32, i32)> {
-        if self.exists {
-            Some((self.x, self.y))
-        } else {
-            None
-        }
+    /// The colour of `player`'s snake, repeating after the fourth.
+    pub fn snake(&self, player: usize) -> pw::graphics::types::Color {
+        self.snakes[player % self.snakes.len()]
     }
+}
 
-    fn set_position(&mut self, x: i32, y: i32) {
-        self.x = x;
-        self.y = y;
-        self.exists = true;
-    }
+trait Renderable {
+    fn render(
+        &self,
+        con: &pw::graphics::Context,
+        g: &mut pw::wgpu_graphics::WgpuGraphics,
+        glyphs: &mut Glyphs,
+    );
 }
 
-struct Grid {
+pub(crate) struct Hud {
+    score: u32,
+    combo: u32,
+    length: usize,
+    high_score: u32,
     width: i32,
-    height: i32,
+    turns: usize,
+    seed: u64,
+    difficulty: Difficulty,
+    moves_per_second: f64,
+    status: Option<String>,
+    /// Label, points and length of every player in a multi-snake match.
+    /// Replaces the single score and length when not empty.
+    players: Vec<(String, u32, usize)>,
+    text_color: pw::graphics::types::Color,
 }
 
-impl Grid {
-    fn new(width: i32, height: i32) -> Grid {
-        Grid { width, height }
+impl Hud {
+    /// The lines shown top left: score, length and turns, and any status.
+    /// Empty lines keep their row free.
+    pub(crate) fn left_column(&self) -> Vec<String> {
+        let score_text = if !self.players.is_empty() {
+            self.players
+                .iter()
+                .map(|(label, points, length)| format!("{}: {} ({})", label, points, length))
+                .collect::<Vec<_>>()
+                .join("  ")
+        } else if self.combo > 1 {
+            format!("Score: {} x{}", self.score, self.combo)
+        } else {
+            format!("Score: {}", self.score)
+        };
+        let turns_text = if self.players.is_empty() {
+            format!("Length: {} Turns: {}", self.length, self.turns)
+        } else {
+            String::new()
+        };
+        let mut lines = vec![score_text, turns_text];
+        lines.extend(self.status.clone());
+        lines
     }
 
-    fn draw(&self, con: &pw::graphics::Context, g: &mut pw::wgpu_graphics::WgpuGraphics) {
-        draw_rectangle(BORDER_COLOR, 0, 0, self.width, 1, con, g);
-        draw_rectangle(BORDER_COLOR, 0, self.height - 1, self.width, 1, con, g);
-        draw_rectangle(BORDER_COLOR, 0, 0, 1, self.height, con, g);
-        draw_rectangle(BORDER_COLOR, self.width - 1, 0, 1, self.height, con, g);
+    /// The lines shown top right: high score, seed and speed.
+    pub(crate) fn right_column(&self) -> Vec<String> {
+        vec![
+            format!("High: {}", self.high_score),
+            format!("Seed: {}", self.seed),
+            format!("{} {:.1}/s", self.difficulty.name(), self.moves_per_second),
+        ]
     }
 }
 
-struct Hud {
-    score: usize,
-    high_score: u32,
-    width: i32,
-    turns: usize,
-}
-
 impl Renderable for Hud {
     fn render(
         &self,
@@ -108,36 +184,93 @@ impl Renderable for Hud {
         g: &mut pw::wgpu_graphics::WgpuGraphics,
         glyphs: &mut Glyphs,
     ) {
-        let score_text = format!("Score: {}", self.score);
-        let high_text = format!("High: {}", self.high_score);
-
         let text_y = BLOCK_SIZE - 4.0;
         let score_x = BLOCK_SIZE + 5.0;
         let high_x = (self.width as f64) * BLOCK_SIZE - 80.0;
 
-        let transform = con.transform.trans(score_x, text_y + FONT_SIZE as f64);
-        pw::graphics::text::Text::new_color(TEXT_COLOR, FONT_SIZE)
-            .draw(&score_text, glyphs, &con.draw_state, transform, g)
-            .unwrap_or(());
-
-        let transform = con.transform.trans(high_x, text_y + FONT_SIZE as f64);
-        pw::graphics::text::Text::new_color(TEXT_COLOR, FONT_SIZE)
-            .draw(&high_text, glyphs, &con.draw_state, transform, g)
-            .unwrap_or(());
-
-        let turns_text = format!("Turns: {}", self.turns);
-        let turns_y = text_y + (FONT_SIZE as f64) + 6.0;
-        let transform = con.transform.trans(score_x, turns_y + FONT_SIZE as f64);
-        pw::graphics::text::Text::new_color(TEXT_COLOR, FONT_SIZE)
-            .draw(&turns_text, glyphs, &con.draw_state, transform, g)
-            .unwrap_or(());
+        let columns = [(score_x, self.left_column()), (high_x, self.right_column())];
+        for (x, lines) in columns {
+            for (row, line) in lines.iter().enumerate() {
+                if line.is_empty() {
+                    continue;
+                }
+                let y = text_y + (row as f64) * (FONT_SIZE as f64 + 6.0) + FONT_SIZE as f64;
+                let transform = con.transform.trans(x, y);
+                pw::graphics::text::Text::new_color(self.text_color, FONT_SIZE)
+                    .draw(line, glyphs, &con.draw_state, transform, g)
+                    .unwrap_or(());
+            }
+        }
     }
 }
 
-struct Overlay {
+pub(crate) struct Overlay {
     state: GameState,
     width: i32,
     height: i32,
+    result: Option<MatchResult>,
+    page: Page,
+    /// The items of the open menu page, with their values.
+    menu: Vec<String>,
+    /// For the rebind screen.
+    key_map: KeyMap,
+    rebind: Option<Rebind>,
+    text_color: pw::graphics::types::Color,
+    bar_color: pw::graphics::types::Color,
+}
+
+impl Overlay {
+    /// The headline for the current state, if it has one.
+    pub(crate) fn title(&self) -> Option<String> {
+        match &self.state {
+            GameState::Menu if self.rebind.is_some() => Some("Keys".to_string()),
+            GameState::Menu => Some(self.page.title().to_string()),
+            GameState::Playing => None,
+            GameState::Paused => Some("Paused".to_string()),
+            GameState::GameOver { .. } => self.result.map(|result| match result {
+                MatchResult::Winner(index) => format!("Player {} wins!", index + 1),
+                MatchResult::Draw => "Draw!".to_string(),
+            }),
+            GameState::Victory { .. } => Some("Board cleared!".to_string()),
+        }
+    }
+
+    /// The lines shown below the title.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        match &self.state {
+            GameState::Menu => match self.rebind {
+                Some(rebind) => self.rebind_lines(rebind),
+                None => self.menu.clone(),
+            },
+            GameState::Playing | GameState::Paused => Vec::new(),
+            GameState::GameOver { final_score } | GameState::Victory { final_score } => {
+                vec![format!("Final: {}", final_score)]
+            }
+        }
+    }
+}
+
+impl Overlay {
+    /// A hint, then a window of actions around the selected one.
+    fn rebind_lines(&self, rebind: Rebind) -> Vec<String> {
+        let selected = Action::ALL[rebind.selected];
+        let mut lines = if rebind.waiting {
+            vec![format!("Press a key for {}", selected.name()), "Esc: cancel".to_string()]
+        } else {
+            vec!["Enter: change, Backspace: default".to_string(), "Esc: back".to_string()]
+        };
+        let first = rebind
+            .selected
+            .saturating_sub(REBIND_ROWS / 2)
+            .min(Action::ALL.len() - REBIND_ROWS);
+        for (index, action) in Action::ALL.iter().enumerate().skip(first).take(REBIND_ROWS) {
+            let keys: Vec<String> = self.key_map.keys(*action).iter().map(|key| key_name(*key)).collect();
+            let keys = if keys.is_empty() { "unbound".to_string() } else { keys.join(", ") };
+            let marker = if index == rebind.selected { ">" } else { " " };
+            lines.push(format!("{} {}: {}", marker, action.name(), keys));
+        }
+        lines
+    }
 }
 
 impl Renderable for Overlay {
@@ -147,178 +280,925 @@ impl Renderable for Overlay {
         g: &mut pw::wgpu_graphics::WgpuGraphics,
         glyphs: &mut Glyphs,
     ) {
-        match &self.state {
-            GameState::Menu => {
-                draw_rectangle(PAUSE_COLOR, 0, 0, self.width, self.height, con, g);
-                let title = "SNAKE";
-                let hint = "Press Enter to start";
-                let center_x = (self.width as f64) * BLOCK_SIZE * 0.5;
-                let center_y = (self.height as f64) * BLOCK_SIZE * 0.5;
-                let title_transform = con.transform.trans(center_x - 40.0, center_y - 10.0);
-                pw::graphics::text::Text::new_color(TEXT_COLOR, 24)
-                    .draw(title, glyphs, &con.draw_state, title_transform, g)
-                    .unwrap_or(());
-                let hint_transform = con.transform.trans(center_x - 90.0, center_y + 18.0);
-                pw::graphics::text::Text::new_color(TEXT_COLOR, FONT_SIZE)
-                    .draw(hint, glyphs, &con.draw_state, hint_transform, g)
-                    .unwrap_or(());
-            }
-            GameState::Playing => {}
+        let center_x = (self.width as f64) * BLOCK_SIZE * 0.5;
+        let center_y = (self.height as f64) * BLOCK_SIZE * 0.5;
+        // Where the title and the first line go, relative to the centre.
+        let (title_at, lines_at) = match &self.state {
+            GameState::Playing => return,
             GameState::Paused => {
                 draw_rectangle(PAUSE_COLOR, 0, 0, self.width, self.height, con, g);
                 let center_x = self.width / 2;
                 let center_y = self.height / 2;
-                draw_rectangle(BORDER_COLOR, center_x - 1, center_y - 1, 1, 3, con, g);
-                draw_rectangle(BORDER_COLOR, center_x + 1, center_y - 1, 1, 3, con, g);
-            }
-            GameState::GameOver { final_score, .. } => {
-            draw_rectangle(GAMEOVER_COLOR, 0, 0, self.width, self.height, con, g);
-            let msg = format!("Final: {}", final_score);
-            let center_x = (self.width as f64) * BLOCK_SIZE * 0.5;
-            let center_y = (self.height as f64) * BLOCK_SIZE * 0.5;
-            let transform = con.transform.trans(center_x - 40.0, center_y);
-            pw::graphics::text::Text::new_color(TEXT_COLOR, FONT_SIZE)
-                .draw(&msg, glyphs, &con.draw_state, transform, g)
-                .unwrap_or(());
+                draw_rectangle(self.bar_color, center_x - 1, center_y - 1, 1, 3, con, g);
+                draw_rectangle(self.bar_color, center_x + 1, center_y - 1, 1, 3, con, g);
+                return;
+            }
+            // Menu pages are lists, so they start near the top left under a
+            // roughly centred title.
+            GameState::Menu => {
+                draw_rectangle(PAUSE_COLOR, 0, 0, self.width, self.height, con, g);
+                let title_width = self.title().map_or(0, |title| title.len()) as f64 * 14.0;
+                ((-title_width / 2.0, 40.0 - center_y), (BLOCK_SIZE - center_x, 70.0 - center_y))
+            }
+            GameState::GameOver { .. } => {
+                draw_rectangle(GAMEOVER_COLOR, 0, 0, self.width, self.height, con, g);
+                ((-50.0, -28.0), (-40.0, 0.0))
+            }
+            GameState::Victory { .. } => {
+                draw_rectangle(VICTORY_COLOR, 0, 0, self.width, self.height, con, g);
+                ((-70.0, -28.0), (-40.0, 0.0))
             }
+        };
+
+        if let Some(title) = self.title() {
+            let transform = con.transform.trans(center_x + title_at.0, center_y + title_at.1);
+            pw::graphics::text::Text::new_color(self.text_color, 24)
+                .draw(&title, glyphs, &con.draw_state, transform, g)
+                .unwrap_or(());
+        }
+        for (row, line) in self.lines().iter().enumerate() {
+            let y = center_y + lines_at.1 + 22.0 * row as f64;
+            let transform = con.transform.trans(center_x + lines_at.0, y);
+            pw::graphics::text::Text::new_color(self.text_color, FONT_SIZE)
+                .draw(line, glyphs, &con.draw_state, transform, g)
+                .unwrap_or(());
         }
     }
 }
 
-impl Renderable for Snake {
-    fn render(
-        &self,
-        con: &pw::graphics::Context,
-        g: &mut pw::wgpu_graphics::WgpuGraphics,
-        _glyphs: &mut Glyphs,
-    ) {
-        Snake::draw(self, con, g);
-    }
+/// A snake drawn in its player's colour.
+struct PlayerSnake<'a> {
+    snake: &'a Snake,
+    color: pw::graphics::types::Color,
 }
 
-impl Renderable for &Snake {
+impl Renderable for PlayerSnake<'_> {
     fn render(
         &self,
         con: &pw::graphics::Context,
         g: &mut pw::wgpu_graphics::WgpuGraphics,
         _glyphs: &mut Glyphs,
     ) {
-        Snake::draw(*self, con, g);
+        for (x, y) in self.snake.body_positions() {
+            draw_block(self.color, x, y, con, g);
+        }
     }
 }
 
-impl Renderable for Food {
+/// Draws every segment part of the way between its previous and current cell.
+struct InterpolatedSnake<'a> {
+    snake: &'a Snake,
+    alpha: f64,
+    color: pw::graphics::types::Color,
+}
+
+impl Renderable for InterpolatedSnake<'_> {
     fn render(
         &self,
         con: &pw::graphics::Context,
         g: &mut pw::wgpu_graphics::WgpuGraphics,
         _glyphs: &mut Glyphs,
     ) {
-        self.draw(con, g);
+        let current = self.snake.body_positions();
+        let previous = self.snake.previous_positions();
+        for ((x, y), (px, py)) in current.into_iter().zip(previous) {
+            // A segment that just wrapped around the board jumps instead of sliding across it.
+            if (x - px).abs() > 1 || (y - py).abs() > 1 {
+                draw_block(self.color, x, y, con, g);
+                continue;
+            }
+            let ix = px as f64 + (x - px) as f64 * self.alpha;
+            let iy = py as f64 + (y - py) as f64 * self.alpha;
+            draw_block_at(self.color, ix, iy, con, g);
+        }
     }
 }
 
-impl Renderable for &Food {
+/// The food on the board, each in its kind's colour.
+struct Foods<'a> {
+    foods: &'a [Food],
+    palette: &'a Palette,
+}
+
+impl Renderable for Foods<'_> {
     fn render(
         &self,
         con: &pw::graphics::Context,
         g: &mut pw::wgpu_graphics::WgpuGraphics,
         _glyphs: &mut Glyphs,
     ) {
-        self.draw(con, g);
+        for food in self.foods {
+            let (x, y) = food.position();
+            draw_block(self.palette.food(food.kind()), x, y, con, g);
+        }
     }
 }
 
-impl Renderable for Grid {
+/// The edge of the board: solid walls, or a dashed line when the snake wraps.
+struct Border<'a> {
+    grid: &'a Grid,
+    mode: BoardMode,
+    palette: &'a Palette,
+}
+
+impl Renderable for Border<'_> {
     fn render(
         &self,
         con: &pw::graphics::Context,
         g: &mut pw::wgpu_graphics::WgpuGraphics,
         _glyphs: &mut Glyphs,
     ) {
-        self.draw(con, g);
+        let (width, height) = (self.grid.width, self.grid.height);
+        match self.mode {
+            BoardMode::Walls => {
+                draw_rectangle(self.palette.border, 0, 0, width, 1, con, g);
+                draw_rectangle(self.palette.border, 0, height - 1, width, 1, con, g);
+                draw_rectangle(self.palette.border, 0, 0, 1, height, con, g);
+                draw_rectangle(self.palette.border, width - 1, 0, 1, height, con, g);
+            }
+            BoardMode::Wrap => {
+                for x in (0..width).step_by(2) {
+                    draw_block(self.palette.wrap_border, x, 0, con, g);
+                    draw_block(self.palette.wrap_border, x, height - 1, con, g);
+                }
+                for y in (0..height).step_by(2) {
+                    draw_block(self.palette.wrap_border, 0, y, con, g);
+                    draw_block(self.palette.wrap_border, width - 1, y, con, g);
+                }
+            }
+        }
     }
 }
 
-impl Renderable for &Grid {
+/// Interior obstacles of the current level, drawn like the border.
+struct Walls<'a> {
+    cells: &'a [(i32, i32)],
+    color: pw::graphics::types::Color,
+}
+
+impl Renderable for Walls<'_> {
     fn render(
         &self,
         con: &pw::graphics::Context,
         g: &mut pw::wgpu_graphics::WgpuGraphics,
         _glyphs: &mut Glyphs,
     ) {
-        self.draw(con, g);
+        for &(x, y) in self.cells {
+            draw_block(self.color, x, y, con, g);
+        }
     }
 }
 
-pub struct Game {
-    snake: Snake,
+#[cfg(feature = "debug_draw")]
+fn draw_direction_indicator(
+    snake: &Snake,
+    con: &pw::graphics::Context,
+    g: &mut pw::wgpu_graphics::WgpuGraphics,
+    color: pw::graphics::types::Color,
+) {
+    let (hx, hy) = snake.head_position();
+    let x = to_coord(hx);
+    let y = to_coord(hy);
+    let thickness = BLOCK_SIZE * 0.2;
+
+    let rect = match snake.head_direction() {
+        Direction::Up => [x, y, BLOCK_SIZE, thickness],
+        Direction::Down => [x, y + BLOCK_SIZE - thickness, BLOCK_SIZE, thickness],
+        Direction::Left => [x, y, thickness, BLOCK_SIZE],
+        Direction::Right => [x + BLOCK_SIZE - thickness, y, thickness, BLOCK_SIZE],
+    };
+
+    pw::graphics::rectangle(color, rect, con.transform, g);
+}
 
-    food: Food,
-    grid: Grid,
+#[cfg(feature = "tui")]
+/// Everything on screen for one frame, for front ends that draw the board
+/// themselves instead of through piston.
+pub(crate) struct Scene<'a> {
+    pub grid: &'a Grid,
+    pub mode: BoardMode,
+    /// The snakes still in play, each with its colour.
+    pub snakes: Vec<(&'a Snake, pw::graphics::types::Color)>,
+    pub foods: &'a [Food],
+    pub walls: &'a [(i32, i32)],
+    pub hud: Hud,
+    pub overlay: Overlay,
+    pub palette: &'a Palette,
+}
 
-    state: GameState,
-    waiting_time: f64,
+/// The preset after `current` in [`BOARD_SIZES`] or, with a negative `step`,
+/// before it, wrapping at either end. Sizes that are no preset, say from a
+/// level, go to the nearest preset in that direction.
+fn next_board_size(current: (i32, i32), step: i32) -> (i32, i32) {
+    let area = |(width, height): (i32, i32)| width * height;
+    let count = BOARD_SIZES.len();
+    let index = match BOARD_SIZES.iter().position(|size| *size == current) {
+        Some(index) if step < 0 => (index + count - 1) % count,
+        Some(index) => (index + 1) % count,
+        None if step < 0 => BOARD_SIZES.iter().rposition(|size| area(*size) < area(current)).unwrap_or(count - 1),
+        None => BOARD_SIZES.iter().position(|size| area(*size) > area(current)).unwrap_or(0),
+    };
+    BOARD_SIZES[index]
+}
+
+/// Where the rebind screen is: the action picked, by index into
+/// [`Action::ALL`], and whether it waits for the new key.
+#[derive(Clone, Copy, Default)]
+struct Rebind {
+    selected: usize,
+    waiting: bool,
+}
+
+/// Replay playback controls layered over a [`ReplayPlayer`].
+struct Playback {
+    player: ReplayPlayer,
+    paused: bool,
+    speed: u32,
+}
+
+/// Who steers one snake.
+enum Pilot {
+    /// Someone pressing keys or gamepad buttons.
+    Human(KeyboardController),
+    /// The autopilot, switched on and off during play.
+    Autopilot(Autopilot),
+    /// A bot given the seat at startup.
+    Bot(Box<dyn Controller>),
+}
+
+impl Pilot {
+    fn human() -> Pilot {
+        Pilot::Human(KeyboardController::new())
+    }
+
+    fn controller(&mut self) -> &mut dyn Controller {
+        match self {
+            Pilot::Human(keyboard) => keyboard,
+            Pilot::Autopilot(autopilot) => autopilot,
+            Pilot::Bot(bot) => bot.as_mut(),
+        }
+    }
+
+    /// The name shown for a pilot that is not a person.
+    fn bot_name(&self) -> Option<&str> {
+        match self {
+            Pilot::Human(_) => None,
+            Pilot::Autopilot(autopilot) => Some(autopilot.name()),
+            Pilot::Bot(bot) => Some(bot.name()),
+        }
+    }
+}
+
+/// The piston front end: drives a [`World`] from window events and adds
+/// rendering, sound and the persisted high score on top of it.
+pub struct Game {
+    world: World,
+    /// A local multi-snake match, played instead of `world` when set.
+    arena: Option<Arena>,
+    /// Who steers each snake, by player. People unless a bot was picked.
+    pilots: Vec<Pilot>,
+
+    scheduler: TickScheduler,
+    interpolate: bool,
+    palette: Palette,
+    key_map: KeyMap,
+    config_path: Option<PathBuf>,
+    menu: Menu,
+    /// The high scores listed on their menu page, read when it opens.
+    high_score_table: Vec<(String, u32)>,
+    /// The key rebinding screen, shown over the menu while open.
+    rebind: Option<Rebind>,
+    quit_requested: bool,
+    /// The last thing reported to the player and the seconds it has left in
+    /// the HUD. Printing would scribble over the terminal front end.
+    message: Option<(String, f64)>,
+    restart_timer: f64,
     high_score: u32,
+    high_score_path: Option<PathBuf>,
+    speed_curve: Option<SpeedCurve>,
+    save_path: PathBuf,
+    save_available: bool,
+    record_path: Option<PathBuf>,
+    recording: Option<Replay>,
+    playback: Option<Playback>,
     sound_player: Option<SoundPlayer>,
     last_dt: f64,
     fps: f64,
     fps_accum: f64,
     fps_frames: u32,
-    tick_count: u64,
+    frame_count: u64,
 }
 
 impl Game {
-    pub fn new(width: i32, height: i32, sound_player: Option<SoundPlayer>) -> Game {
+    pub fn new(width: i32, height: i32, seed: u64, sound_player: Option<SoundPlayer>) -> Game {
         if let Some(ref player) = sound_player {
             player.play_start();
         }
         Game {
-            snake: Snake::new(2, 2),
-            waiting_time: 0.0,
-            food: Food::new(6, 4),
-            grid: Grid::new(width, height),
-            state: GameState::Menu,
-            high_score: persistence::load_high_score(),
+            world: World::new(width, height, seed),
+            arena: None,
+            pilots: vec![Pilot::human()],
+            scheduler: TickScheduler::new(Difficulty::default().curve().start_period),
+            interpolate: false,
+            palette: Palette::default(),
+            key_map: KeyMap::default(),
+            config_path: None,
+            menu: Menu::default(),
+            high_score_table: Vec::new(),
+            rebind: None,
+            quit_requested: false,
+            message: None,
+            restart_timer: 0.0,
+            high_score: 0,
+            high_score_path: None,
+            speed_curve: None,
+            save_path: PathBuf::from(SAVE_FILE),
+            save_available: Path::new(SAVE_FILE).exists(),
+            record_path: None,
+            recording: None,
+            playback: None,
             sound_player,
             last_dt: 0.0,
             fps: 0.0,
             fps_accum: 0.0,
             fps_frames: 0,
-            tick_count: 0,
+            frame_count: 0,
+        }
+    }
+
+    /// Writes the running game to the save file. Menu and game over are not saved.
+    pub fn save_game(&mut self) {
+        if self.playback.is_some()
+            || !matches!(self.world.state(), GameState::Playing | GameState::Paused)
+        {
+            return;
+        }
+        match save::save_to(&self.save_path, &self.world.to_save()) {
+            Ok(()) => {
+                self.save_available = true;
+                self.notify(format!("Game saved to {}", self.save_path.display()));
+            }
+            Err(e) => self.notify(format!("Failed to save game: {}", e)),
+        }
+    }
+
+    /// Replaces the current game with the saved one. It resumes paused so the
+    /// player can get their bearings before pressing Space.
+    pub fn load_game(&mut self) -> bool {
+        match save::load_from(&self.save_path) {
+            Ok(saved) => {
+                // A resumed game did not start from a known RNG state, so it cannot be replayed.
+                self.recording = None;
+                self.arena = None;
+                self.world = World::from_save(&saved);
+                self.load_high_score();
+                if *self.world.state() == GameState::Playing {
+                    self.world.toggle_pause();
+                }
+                self.scheduler.reset();
+                self.restart_timer = 0.0;
+                true
+            }
+            Err(e) => {
+                self.notify(format!("Failed to load saved game: {}", e));
+                false
+            }
+        }
+    }
+
+    /// Records every game from now on, overwriting `path` when a game ends.
+    pub fn record_to(&mut self, path: PathBuf) {
+        self.record_path = Some(path);
+    }
+
+    /// Switches to watching `replay`. Space pauses, F cycles the speed and
+    /// N steps a single tick while paused.
+    pub fn play_replay(&mut self, replay: Replay) {
+        let player = ReplayPlayer::new(replay);
+        self.world = player.world();
+        self.load_high_score();
+        self.scheduler.reset();
+        self.recording = None;
+        self.playback = Some(Playback {
+            player,
+            paused: false,
+            speed: 1,
+        });
+    }
+
+    /// Flushes what needs to survive the window closing. The front end has
+    /// given the screen back by then, so what this reports is printed.
+    pub fn shutdown(&mut self) {
+        // Whatever the HUD still shows has been seen already.
+        self.message = None;
+        self.finish_recording();
+        self.print_message();
+        self.save_game();
+        self.print_message();
+    }
+
+    /// Shows `message` in the HUD for a few seconds.
+    fn notify(&mut self, message: String) {
+        self.message = Some((message, MESSAGE_TIME));
+    }
+
+    fn print_message(&mut self) {
+        if let Some((message, _)) = self.message.take() {
+            println!("{}", message);
+        }
+    }
+
+    fn begin_recording(&mut self) {
+        if self.record_path.is_some() && self.playback.is_none() {
+            self.recording = Some(Replay::start(&self.world));
+        }
+    }
+
+    fn finish_recording(&mut self) {
+        let (Some(path), Some(recording)) = (&self.record_path, self.recording.take()) else {
+            return;
+        };
+        let message = match replay::save_to(path, &recording) {
+            Ok(()) => format!("Replay saved to {}", path.display()),
+            Err(e) => format!("Failed to save replay: {}", e),
+        };
+        self.notify(message);
+    }
+
+    /// Switches between walled and wrapping boards. Only possible from the menu.
+    pub fn set_mode(&mut self, mode: BoardMode) {
+        if !self.menu_open() {
+            return;
+        }
+        self.world.set_mode(mode);
+        self.load_high_score();
+        self.rebuild_arena();
+    }
+
+    /// Replaces the board with an empty one of the given size, keeping the
+    /// seed, board mode and difficulty. Only possible from the menu.
+    pub fn set_board_size(&mut self, width: i32, height: i32) {
+        if !self.menu_open() {
+            return;
+        }
+        let (mode, difficulty) = (self.world.mode(), self.world.difficulty());
+        self.world = World::new(width, height, self.world.seed());
+        self.world.set_mode(mode);
+        self.world.set_difficulty(difficulty);
+        self.rebuild_arena();
+    }
+
+    /// Replaces the board with `level`, keeping the seed and board mode. Only
+    /// possible from the menu.
+    pub fn load_level(&mut self, level: Level) {
+        if !self.menu_open() {
+            return;
+        }
+        let mode = self.world.mode();
+        self.world = World::with_level(level, GameRng::new(self.world.seed()));
+        self.world.set_mode(mode);
+        self.rebuild_arena();
+    }
+
+    /// Switches between a single player and a local match between `players`
+    /// snakes on the same board. Only possible from the menu. Returns false
+    /// if `players` is zero or the board has no room for that many snakes.
+    pub fn set_players(&mut self, players: usize) -> bool {
+        if !self.menu_open() {
+            return false;
+        }
+        match players {
+            1 => {
+                self.arena = None;
+                true
+            }
+            _ if players >= 2 => {
+                self.ensure_pilots(players);
+                self.arena = Arena::new(
+                    self.world.level().clone(),
+                    players,
+                    GameRng::new(self.world.seed()),
+                    self.world.mode(),
+                    self.world.difficulty(),
+                );
+                self.arena.is_some()
+            }
+            _ => false,
+        }
+    }
+
+    fn players(&self) -> usize {
+        match &self.arena {
+            Some(arena) => arena.players().len(),
+            None => 1,
         }
     }
 
+    /// Carries menu changes over to a pending multi-snake match.
+    fn rebuild_arena(&mut self) {
+        let players = self.players();
+        if self.arena.is_some() && !self.set_players(players) {
+            self.notify(format!("This board has no room for {} snakes, back to one player", players));
+            self.arena = None;
+        }
+    }
+
+    /// Hands `player`'s snake to `controller`, e.g. a bot from
+    /// [`snake::bots`]. Players without one use the keyboard.
+    pub fn set_controller(&mut self, player: usize, controller: Box<dyn Controller>) {
+        self.ensure_pilots(player + 1);
+        self.pilots[player] = Pilot::Bot(controller);
+    }
+
+    /// Hands player one's snake to the [`Autopilot`] and back to the keyboard.
+    pub fn toggle_autopilot(&mut self) {
+        self.pilots[0] = match self.pilots[0] {
+            Pilot::Autopilot(_) => Pilot::human(),
+            _ => Pilot::Autopilot(Autopilot::new()),
+        };
+    }
+
+    fn ensure_pilots(&mut self, players: usize) {
+        while self.pilots.len() < players {
+            self.pilots.push(Pilot::human());
+        }
+    }
+
+    /// "P1", followed by the bot's name if a bot steers the player.
+    fn player_label(&self, player: usize) -> String {
+        match self.pilots.get(player).and_then(Pilot::bot_name) {
+            Some(name) => format!("P{} {}", player + 1, name),
+            None => format!("P{}", player + 1),
+        }
+    }
+
+    fn menu_open(&self) -> bool {
+        let state = match &self.arena {
+            Some(arena) => arena.state(),
+            None => self.world.state(),
+        };
+        *state == GameState::Menu
+    }
+
+    /// Width and height of the board in cells, which the window is sized to.
+    pub fn board_size(&self) -> (i32, i32) {
+        (self.world.grid().width, self.world.grid().height)
+    }
+
+    pub fn level(&self) -> &Level {
+        self.world.level()
+    }
+
+    /// Where high scores are read from and written to. Without a path they
+    /// only last for the session.
+    pub fn set_high_score_path(&mut self, path: Option<PathBuf>) {
+        self.high_score_path = path;
+        self.load_high_score();
+    }
+
+    fn load_high_score(&mut self) {
+        let loaded = match &self.high_score_path {
+            Some(path) => persistence::try_load_high_score(path, &self.high_score_bucket()),
+            None => Ok(0),
+        };
+        self.high_score = loaded.unwrap_or_else(|e| {
+            self.notify(format!("Failed to load high score: {}", e));
+            0
+        });
+    }
+
+    /// Picks the speed preset. Only possible from the menu.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        if !self.menu_open() {
+            return;
+        }
+        self.world.set_difficulty(difficulty);
+        self.load_high_score();
+        self.rebuild_arena();
+    }
+
+    /// Replaces the difficulty's speed curve, or restores it with `None`.
+    pub fn set_speed_curve(&mut self, curve: Option<SpeedCurve>) {
+        self.speed_curve = curve;
+    }
+
+    /// High scores are tracked separately for every board mode and difficulty.
+    fn high_score_bucket(&self) -> String {
+        format!("{}-{}", self.world.mode().name(), self.world.difficulty().name())
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn set_key_map(&mut self, key_map: KeyMap) {
+        self.key_map = key_map;
+    }
+
+    /// The config file that settings changed in the menu and rebound keys
+    /// are saved to. Without a path they only last for the session.
+    pub fn set_config_path(&mut self, path: Option<PathBuf>) {
+        self.config_path = path;
+    }
+
+    /// Smooths snake movement between ticks instead of jumping cell by cell.
+    pub fn set_interpolation(&mut self, enabled: bool) {
+        self.interpolate = enabled;
+    }
+
     pub fn key_pressed(&mut self, key: pw::Key) {
-        let dir = match (key, &self.state) {
-            (_, GameState::GameOver { .. }) => return,
-            (pw::Key::Return | pw::Key::Space, GameState::Menu) => {
-                self.state = GameState::Playing;
-                return;
+        if self.rebind.is_some() {
+            self.rebind_key_pressed(key);
+            return;
+        }
+        let actions = self.key_map.actions(key);
+        self.perform(&actions);
+    }
+
+    /// Carries out whichever of `actions` fits the current screen, such as
+    /// the actions bound to a key that was just pressed.
+    pub fn perform(&mut self, actions: &[Action]) {
+        // The rebind screen only listens to the keyboard.
+        if self.rebind.is_some() {
+            return;
+        }
+        // In the menu the quit key steps back from the other pages first.
+        if actions.contains(&Action::Quit) && !self.menu_open() {
+            self.quit_requested = true;
+            return;
+        }
+        if self.playback.is_some() {
+            self.playback_action(actions);
+            return;
+        }
+        if self.arena.is_some() {
+            self.arena_action(actions);
+            return;
+        }
+
+        let has = |action| actions.contains(&action);
+        match self.world.state().clone() {
+            GameState::GameOver { .. } | GameState::Victory { .. } => {
+                if has(Action::Restart) {
+                    self.restart();
+                }
             }
-            (pw::Key::Space, GameState::Playing) => {
-                self.state = GameState::Paused;
-                return;
+            GameState::Menu => self.menu_action(actions),
+            state @ (GameState::Playing | GameState::Paused) => {
+                if has(Action::Pause) {
+                    self.world.toggle_pause();
+                } else if has(Action::Save) {
+                    self.save_game();
+                } else if has(Action::ToggleAutopilot) {
+                    self.toggle_autopilot();
+                } else if has(Action::Restart) {
+                    self.restart();
+                } else if state == GameState::Playing {
+                    let turn = actions.iter().filter_map(Action::turn).find(|(player, _)| *player == 0);
+                    if let Some((_, dir)) = turn {
+                        let heading = self.world.snake().head_direction();
+                        if let Pilot::Human(keyboard) = &mut self.pilots[0] {
+                            keyboard.input(dir, heading);
+                        }
+                    }
+                }
             }
-            (pw::Key::Space, GameState::Paused) => {
-                self.state = GameState::Playing;
-                return;
+        }
+    }
+
+    /// Menu actions shared by single-player and multi-snake games. The
+    /// shortcut keys change their setting straight away; otherwise Up and
+    /// Down move through the items, Left and Right change the selected value
+    /// and Start picks the item.
+    fn menu_action(&mut self, actions: &[Action]) {
+        let shortcut = actions.iter().find_map(|action| match action {
+            Action::Load => Some(Item::Resume),
+            Action::CycleMode => Some(Item::Mode),
+            Action::CycleDifficulty => Some(Item::Difficulty),
+            Action::CyclePlayers => Some(Item::Players),
+            Action::Rebind => Some(Item::Keys),
+            _ => None,
+        });
+        if let Some(item) = shortcut {
+            self.choose(item, 1);
+            return;
+        }
+
+        let items = self.menu.items(self.save_available);
+        let selected = self.menu.selected(&items);
+        if actions.contains(&Action::Quit) {
+            match self.menu.page() {
+                Page::Main => self.quit_requested = true,
+                _ => self.menu.back(),
             }
-            (_, GameState::Paused) => return,
-            (pw::Key::Up | pw::Key::W, _) => Some(Direction::Up),
-            (pw::Key::Down | pw::Key::S, _) => Some(Direction::Down),
-            (pw::Key::Left | pw::Key::A, _) => Some(Direction::Left),
-            (pw::Key::Right | pw::Key::D, _) => Some(Direction::Right),
-            _ => Some(self.snake.head_direction()),
+        } else if actions.contains(&Action::Start) {
+            self.choose(selected, 1);
+        } else if let Some((_, dir)) = actions.iter().find_map(Action::turn) {
+            match dir {
+                Direction::Up => self.menu.step(&items, -1),
+                Direction::Down => self.menu.step(&items, 1),
+                Direction::Left if selected.has_value() => self.choose(selected, -1),
+                Direction::Right if selected.has_value() => self.choose(selected, 1),
+                _ => {}
+            }
+        }
+    }
+
+    /// Picks `item`, stepping its value forwards or, with a negative `step`,
+    /// backwards. Changed settings are saved for the next run.
+    fn choose(&mut self, item: Item, step: i32) {
+        match item {
+            Item::Play => self.start(),
+            Item::Resume => {
+                self.load_game();
+            }
+            Item::Mode => {
+                let mode = match self.world.mode() {
+                    BoardMode::Walls => BoardMode::Wrap,
+                    BoardMode::Wrap => BoardMode::Walls,
+                };
+                self.set_mode(mode);
+                self.save_settings(&[("board.mode", Some(mode.name().into()))]);
+            }
+            Item::Difficulty => {
+                let current = self.world.difficulty();
+                let difficulty = if step < 0 { current.previous() } else { current.next() };
+                self.set_difficulty(difficulty);
+                self.save_settings(&[("speed.difficulty", Some(difficulty.name().into()))]);
+            }
+            Item::BoardSize => {
+                let (width, height) = next_board_size(self.board_size(), step);
+                self.set_board_size(width, height);
+                self.save_settings(&[
+                    ("board.width", Some(i64::from(width).into())),
+                    ("board.height", Some(i64::from(height).into())),
+                    ("board.level", None),
+                ]);
+            }
+            Item::Options => self.menu.open(Page::Options),
+            Item::HighScores => {
+                self.high_score_table = self
+                    .high_score_path
+                    .as_deref()
+                    .and_then(|path| persistence::try_load_all_high_scores(path).ok())
+                    .unwrap_or_default();
+                self.menu.open(Page::HighScores);
+            }
+            Item::Quit => self.quit_requested = true,
+            Item::Players => {
+                let players = if self.players() == 1 { 2 } else { 1 };
+                if self.set_players(players) {
+                    self.save_settings(&[("board.players", Some((players as i64).into()))]);
+                } else {
+                    self.notify("This board has no room for a second snake".to_string());
+                }
+            }
+            Item::SmoothMovement => {
+                self.interpolate = !self.interpolate;
+                self.save_settings(&[("speed.interpolate", Some(self.interpolate.into()))]);
+            }
+            Item::Volume => {
+                let Some(sound_player) = self.sound_player.as_mut() else {
+                    return;
+                };
+                let volume = (sound_player.volume() + VOLUME_STEP * step as f32).clamp(0.0, 1.0);
+                // Rounded so repeated steps land on whole tenths.
+                sound_player.set_volume((volume * 10.0).round() / 10.0);
+                let saved = (f64::from(sound_player.volume()) * 10.0).round() / 10.0;
+                self.save_settings(&[("audio.volume", Some(saved.into()))]);
+            }
+            Item::Keys => self.rebind = Some(Rebind::default()),
+            Item::Back => self.menu.back(),
+        }
+    }
+
+    /// Leaves the menu for a new game or match.
+    fn start(&mut self) {
+        match self.arena.as_mut() {
+            Some(arena) => arena.start(),
+            None => {
+                self.world.start();
+                self.begin_recording();
+            }
+        }
+    }
+
+    /// In a match, keys bound to player two's turns steer player two only,
+    /// so the default arrows are theirs while player one keeps WASD.
+    fn arena_action(&mut self, actions: &[Action]) {
+        let Some(state) = self.arena.as_ref().map(|arena| arena.state().clone()) else {
+            return;
         };
+        let has = |action| actions.contains(&action);
+        match state {
+            GameState::GameOver { .. } | GameState::Victory { .. } => {
+                if has(Action::Restart) {
+                    self.restart();
+                }
+            }
+            GameState::Menu => self.menu_action(actions),
+            GameState::Playing | GameState::Paused => {
+                if has(Action::Pause) {
+                    if let Some(arena) = self.arena.as_mut() {
+                        arena.toggle_pause();
+                    }
+                } else if has(Action::ToggleAutopilot) {
+                    self.toggle_autopilot();
+                } else if has(Action::Restart) {
+                    self.restart();
+                } else if state == GameState::Playing {
+                    let turn = actions.iter().filter_map(Action::turn).max_by_key(|(player, _)| *player);
+                    if let Some((player, dir)) = turn {
+                        let heading = self.arena.as_ref().and_then(|arena| arena.players().get(player));
+                        let heading = heading.map(|p| p.snake().head_direction());
+                        if let (Some(Pilot::Human(keyboard)), Some(heading)) = (self.pilots.get_mut(player), heading) {
+                            keyboard.input(dir, heading);
+                        }
+                    }
+                }
+            }
+        }
+    }
 
-        if let Some(dir) = dir {
-            if dir == self.snake.head_direction().opposite() {
-                return;
+    fn playback_action(&mut self, actions: &[Action]) {
+        let Some(playback) = self.playback.as_mut() else {
+            return;
+        };
+        if actions.contains(&Action::Pause) {
+            playback.paused = !playback.paused;
+        } else if actions.contains(&Action::ReplaySpeed) {
+            playback.speed = if playback.speed >= MAX_PLAYBACK_SPEED {
+                1
+            } else {
+                playback.speed * 2
+            };
+        } else if actions.contains(&Action::ReplayStep) && playback.paused {
+            self.playback_tick();
+        }
+    }
+
+    /// Keys on the rebind screen are fixed so a bad binding cannot lock the
+    /// player out: arrows pick an action, Enter waits for its new key,
+    /// Backspace restores its default and Escape goes back.
+    fn rebind_key_pressed(&mut self, key: pw::Key) {
+        let Some(rebind) = self.rebind.as_mut() else {
+            return;
+        };
+        let action = Action::ALL[rebind.selected];
+        if rebind.waiting {
+            rebind.waiting = false;
+            if key != pw::Key::Escape {
+                self.key_map.set(action, vec![key]);
+                self.save_key_map();
+            }
+            return;
+        }
+        let count = Action::ALL.len();
+        match key {
+            pw::Key::Up => rebind.selected = (rebind.selected + count - 1) % count,
+            pw::Key::Down => rebind.selected = (rebind.selected + 1) % count,
+            pw::Key::Return => rebind.waiting = true,
+            pw::Key::Backspace => {
+                self.key_map.reset(action);
+                self.save_key_map();
             }
+            pw::Key::Escape => self.rebind = None,
+            _ => {}
         }
+    }
 
-        self.update_snake(dir);
+    fn save_key_map(&mut self) {
+        if let Some(path) = &self.config_path {
+            if let Err(e) = config::save_key_map(path, &self.key_map) {
+                self.notify(format!("Failed to save key bindings to {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    fn save_settings(&mut self, settings: &[(&str, Option<toml_edit::Value>)]) {
+        if let Some(path) = &self.config_path {
+            if let Err(e) = config::save_settings(path, settings) {
+                self.notify(format!("Failed to save settings to {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Set once a quit action was performed; the front end then closes.
+    pub fn wants_to_quit(&self) -> bool {
+        self.quit_requested
+    }
+
+    fn playback_tick(&mut self) {
+        let Some(playback) = self.playback.as_mut() else {
+            return;
+        };
+        if let Some(outcome) = playback.player.step(&mut self.world) {
+            self.play_outcome_sound(outcome);
+        }
     }
 
     pub fn draw(
@@ -327,33 +1207,55 @@ impl Game {
         g: &mut pw::wgpu_graphics::WgpuGraphics,
         glyphs: &mut Glyphs,
     ) {
-        let mut renderables: Vec<Box<dyn Renderable + '_>> = Vec::new();
-        renderables.push(Box::new(&self.snake));
-        renderables.push(Box::new(&self.food));
-        renderables.push(Box::new(&self.grid));
-        renderables.push(Box::new(Hud {
-            score: self.snake.len(),
-            high_score: self.high_score,
-            width: self.grid.width,
-            turns: self.snake.corner_count(),
-        }));
-        renderables.push(Box::new(Overlay {
-            state: self.state.clone(),
-            width: self.grid.width,
-            height: self.grid.height,
-        }));
+        if let Some(arena) = &self.arena {
+            self.draw_arena(arena, con, g, glyphs);
+            return;
+        }
+
+        let grid = self.world.grid();
+        let snake: Box<dyn Renderable + '_> =
+            if self.interpolate && *self.world.state() == GameState::Playing {
+                Box::new(InterpolatedSnake {
+                    snake: self.world.snake(),
+                    alpha: self.scheduler.alpha(),
+                    color: self.palette.snake(0),
+                })
+            } else {
+                Box::new(PlayerSnake {
+                    snake: self.world.snake(),
+                    color: self.palette.snake(0),
+                })
+            };
+        let renderables: Vec<Box<dyn Renderable + '_>> = vec![
+            snake,
+            Box::new(Foods {
+                foods: self.world.foods(),
+                palette: &self.palette,
+            }),
+            Box::new(Walls {
+                cells: &self.world.level().walls,
+                color: self.palette.border,
+            }),
+            Box::new(Border {
+                grid,
+                mode: self.world.mode(),
+                palette: &self.palette,
+            }),
+            Box::new(self.hud()),
+            Box::new(self.overlay()),
+        ];
 
         for renderable in renderables {
             renderable.render(con, g, glyphs);
         }
 
         crate::debug_draw!({
-            self.snake.draw_direction_indicator(con, g, DEBUG_COLOR);
+            draw_direction_indicator(self.world.snake(), con, g, DEBUG_COLOR);
 
             let dt_ms = self.last_dt * 1000.0;
             let fps_text = format!("FPS: {:.1}", self.fps);
             let dt_text = format!("dt: {:.2}ms", dt_ms);
-            let tick_text = format!("ticks: {}", self.tick_count);
+            let tick_text = format!("frames: {} ticks: {}", self.frame_count, self.world.tick());
             let base_x = BLOCK_SIZE + 5.0;
             let base_y = BLOCK_SIZE + 12.0;
 
@@ -375,12 +1277,213 @@ impl Game {
         });
     }
 
+    fn draw_arena(
+        &self,
+        arena: &Arena,
+        con: &pw::graphics::Context,
+        g: &mut pw::wgpu_graphics::WgpuGraphics,
+        glyphs: &mut Glyphs,
+    ) {
+        let grid = arena.grid();
+        let mut renderables: Vec<Box<dyn Renderable + '_>> = arena
+            .players()
+            .iter()
+            .enumerate()
+            .filter(|(_, player)| player.is_alive())
+            .map(|(index, player)| {
+                Box::new(PlayerSnake {
+                    snake: player.snake(),
+                    color: self.palette.snake(index),
+                }) as Box<dyn Renderable + '_>
+            })
+            .collect();
+        renderables.extend([
+            Box::new(Foods {
+                foods: arena.foods(),
+                palette: &self.palette,
+            }) as Box<dyn Renderable + '_>,
+            Box::new(Walls {
+                cells: &arena.level().walls,
+                color: self.palette.border,
+            }),
+            Box::new(Border {
+                grid,
+                mode: arena.mode(),
+                palette: &self.palette,
+            }),
+            Box::new(self.hud()),
+            Box::new(self.overlay()),
+        ]);
+
+        for renderable in renderables {
+            renderable.render(con, g, glyphs);
+        }
+    }
+
+    fn hud(&self) -> Hud {
+        let moves_per_second = 1.0 / self.move_period();
+        if let Some(arena) = &self.arena {
+            return Hud {
+                score: arena.top_points(),
+                combo: 1,
+                length: 0,
+                high_score: self.high_score,
+                width: arena.grid().width,
+                turns: 0,
+                seed: arena.seed(),
+                difficulty: arena.difficulty(),
+                moves_per_second,
+                status: self.message.as_ref().map(|(message, _)| message.clone()),
+                players: arena
+                    .players()
+                    .iter()
+                    .enumerate()
+                    .map(|(index, player)| {
+                        (self.player_label(index), player.score().points(), player.snake().len())
+                    })
+                    .collect(),
+                text_color: self.palette.text,
+            };
+        }
+        Hud {
+            score: self.world.score().points(),
+            combo: self.world.score().combo(),
+            length: self.world.snake().len(),
+            high_score: self.high_score,
+            width: self.world.grid().width,
+            turns: self.world.snake().corner_count(),
+            seed: self.world.seed(),
+            difficulty: self.world.difficulty(),
+            moves_per_second,
+            status: self.message.as_ref().map(|(message, _)| message.clone()).or_else(|| match &self.playback {
+                Some(playback) => {
+                    let (done, total) = playback.player.progress();
+                    let mode = if playback.paused { "paused" } else { "playing" };
+                    Some(format!("Replay {}/{} x{} {}", done, total, playback.speed, mode))
+                }
+                None => self.pilots[0].bot_name().map(|name| format!("Bot: {}", name)),
+            }),
+            players: Vec::new(),
+            text_color: self.palette.text,
+        }
+    }
+
+    /// The open menu page, a `>` marking the selected item.
+    fn menu_lines(&self) -> Vec<String> {
+        if !self.menu_open() {
+            return Vec::new();
+        }
+        let mut lines = Vec::new();
+        if self.menu.page() == Page::HighScores {
+            for (bucket, score) in &self.high_score_table {
+                lines.push(format!("  {}: {}", bucket.replace('-', ", "), score));
+            }
+            if self.high_score_table.is_empty() {
+                lines.push("  None yet".to_string());
+            }
+            lines.push(String::new());
+        }
+
+        let items = self.menu.items(self.save_available);
+        let selected = self.menu.selected(&items);
+        for item in items {
+            let value = match item {
+                Item::Mode => Some(self.world.mode().name().to_string()),
+                Item::Difficulty => Some(self.world.difficulty().name().to_string()),
+                Item::BoardSize => {
+                    let (width, height) = self.board_size();
+                    Some(format!("{}x{}", width, height))
+                }
+                Item::Players => Some(self.players().to_string()),
+                Item::SmoothMovement => Some(if self.interpolate { "on" } else { "off" }.to_string()),
+                Item::Volume => Some(match &self.sound_player {
+                    Some(sound_player) => format!("{}%", (sound_player.volume() * 100.0).round()),
+                    None => "muted".to_string(),
+                }),
+                _ => None,
+            };
+            let marker = if item == selected { ">" } else { " " };
+            lines.push(match value {
+                Some(value) => format!("{} {}: {}", marker, item.label(), value),
+                None => format!("{} {}", marker, item.label()),
+            });
+        }
+        lines
+    }
+
+    fn overlay(&self) -> Overlay {
+        if let Some(arena) = &self.arena {
+            return Overlay {
+                state: arena.state().clone(),
+                width: arena.grid().width,
+                height: arena.grid().height,
+                result: arena.result(),
+                page: self.menu.page(),
+                menu: self.menu_lines(),
+                key_map: self.key_map.clone(),
+                rebind: self.rebind,
+                text_color: self.palette.text,
+                bar_color: self.palette.border,
+            };
+        }
+        Overlay {
+            state: self.world.state().clone(),
+            width: self.world.grid().width,
+            height: self.world.grid().height,
+            result: None,
+            page: self.menu.page(),
+            menu: self.menu_lines(),
+            key_map: self.key_map.clone(),
+            rebind: self.rebind,
+            text_color: self.palette.text,
+            bar_color: self.palette.border,
+        }
+    }
+
+    /// The current board, HUD and overlay without any piston drawing.
+    #[cfg(feature = "tui")]
+    pub(crate) fn scene(&self) -> Scene<'_> {
+        if let Some(arena) = &self.arena {
+            return Scene {
+                grid: arena.grid(),
+                mode: arena.mode(),
+                snakes: arena
+                    .players()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, player)| player.is_alive())
+                    .map(|(index, player)| (player.snake(), self.palette.snake(index)))
+                    .collect(),
+                foods: arena.foods(),
+                walls: &arena.level().walls,
+                hud: self.hud(),
+                overlay: self.overlay(),
+                palette: &self.palette,
+            };
+        }
+        Scene {
+            grid: self.world.grid(),
+            mode: self.world.mode(),
+            snakes: vec![(self.world.snake(), self.palette.snake(0))],
+            foods: self.world.foods(),
+            walls: &self.world.level().walls,
+            hud: self.hud(),
+            overlay: self.overlay(),
+            palette: &self.palette,
+        }
+    }
+
     pub fn update(&mut self, delta_time: f64) {
-        self.waiting_time += delta_time;
         self.last_dt = delta_time;
+        if let Some((_, left)) = &mut self.message {
+            *left -= delta_time;
+            if *left <= 0.0 {
+                self.message = None;
+            }
+        }
         self.fps_accum += delta_time;
         self.fps_frames += 1;
-        self.tick_count += 1;
+        self.frame_count += 1;
 
         if self.fps_accum >= 1.0 {
             self.fps = (self.fps_frames as f64) / self.fps_accum;
@@ -388,102 +1491,161 @@ impl Game {
             self.fps_frames = 0;
         }
 
-        if matches!(self.state, GameState::Paused | GameState::Menu) {
+        self.scheduler.set_period(self.move_period());
+
+        if let Some(playback) = &self.playback {
+            if !playback.paused {
+                let speed = playback.speed as f64;
+                for _ in 0..self.scheduler.advance(delta_time * speed) {
+                    self.playback_tick();
+                }
+            }
+            return;
+        }
+        if self.arena.is_some() {
+            self.update_arena(delta_time);
             return;
         }
 
-        if let GameState::GameOver {
-            elapsed,
-            final_score,
-        } = &self.state
-        {
-            let new_elapsed = elapsed + delta_time;
-            if new_elapsed > RESTART_TIME {
-                self.restart();
-            } else {
-                self.state = GameState::GameOver {
-                    elapsed: new_elapsed,
-                    final_score: *final_score,
-                };
+        match self.world.state() {
+            GameState::Paused | GameState::Menu => return,
+            GameState::GameOver { .. } | GameState::Victory { .. } => {
+                self.restart_timer += delta_time;
+                if self.restart_timer > RESTART_TIME {
+                    self.restart();
+                }
+                return;
+            }
+            GameState::Playing => {}
+        }
+
+        for _ in 0..self.scheduler.advance(delta_time) {
+            self.update_snake();
+            if *self.world.state() != GameState::Playing {
+                break;
             }
-            return;
         }
+    }
 
-        if !self.food.exists {
-            self.add_food();
+    fn update_arena(&mut self, delta_time: f64) {
+        let Some(arena) = self.arena.as_mut() else {
+            return;
+        };
+        match arena.state() {
+            GameState::Paused | GameState::Menu => return,
+            GameState::GameOver { .. } | GameState::Victory { .. } => {
+                self.restart_timer += delta_time;
+                if self.restart_timer > RESTART_TIME {
+                    self.restart();
+                }
+                return;
+            }
+            GameState::Playing => {}
         }
 
-        if self.waiting_time > MOVING_PERIOD {
-            self.update_snake(None);
+        for _ in 0..self.scheduler.advance(delta_time) {
+            let Some(arena) = self.arena.as_mut() else {
+                return;
+            };
+            let turns: Vec<Option<Direction>> = self
+                .pilots
+                .iter_mut()
+                .enumerate()
+                .take(arena.players().len())
+                .map(|(player, pilot)| match arena.players()[player].is_alive() {
+                    true => pilot.controller().next_turn(&arena.view(player)),
+                    false => None,
+                })
+                .collect();
+            let outcomes = arena.step_with(&turns);
+            let playing = *arena.state() == GameState::Playing;
+            for outcome in outcomes {
+                self.play_outcome_sound(outcome);
+            }
+            if !playing {
+                self.restart_timer = 0.0;
+                break;
+            }
         }
     }
 
-    pub(crate) fn check_eating(&mut self) {
-        let (head_x, head_y): (i32, i32) = self.snake.head_position();
-        if self.food.exists && self.food.x == head_x && self.food.y == head_y {
-            self.food.exists = false;
-            self.snake.restore_tail();
-
-            if let Some(ref player) = self.sound_player {
-                player.play_eat();
+    fn update_snake(&mut self) {
+        let turn = self.pilots[0].controller().next_turn(&self.world.view());
+        let outcome = self.world.step_with(turn);
+        if outcome != StepOutcome::Idle {
+            if let Some(recording) = self.recording.as_mut() {
+                recording.record(self.world.last_turn());
             }
+        }
 
-            let current_score = self.snake.len() as u32;
-            if current_score > self.high_score {
-                self.high_score = current_score;
-                persistence::save_high_score(self.high_score);
-                println!("New High Score: {}", self.high_score);
+        self.play_outcome_sound(outcome);
+        match outcome {
+            StepOutcome::Ate(_) => self.on_eat(),
+            StepOutcome::Died => {
+                self.restart_timer = 0.0;
+                self.finish_recording();
+            }
+            StepOutcome::Won => {
+                // The winning move may have eaten, so its points still count.
+                self.on_eat();
+                self.restart_timer = 0.0;
+                self.finish_recording();
             }
+            StepOutcome::Moved | StepOutcome::Idle => {}
         }
     }
 
-    pub(crate) fn check_if_snake_alive(&self, dir: Option<Direction>) -> bool {
-        let (next_x, next_y) = self.snake.next_head(dir);
-
-        if self.snake.overlap_tail(next_x, next_y) {
-            return false;
+    fn play_outcome_sound(&self, outcome: StepOutcome) {
+        let Some(ref player) = self.sound_player else {
+            return;
+        };
+        match outcome {
+            StepOutcome::Ate(kind) => player.play_food(kind),
+            StepOutcome::Died => player.play_death(),
+            StepOutcome::Won => player.play_victory(),
+            StepOutcome::Moved | StepOutcome::Idle => {}
         }
-
-        next_x > 0
-            && next_y > 0
-            && next_x < self.grid.width - 1
-            && next_y < self.grid.height - 1
     }
 
-    pub(crate) fn add_food(&mut self) {
-        let mut rng = rand::rng();
-
-        let choice = (1..self.grid.width - 1)
-            .flat_map(|x| (1..self.grid.height - 1).map(move |y| (x, y))) // grid of all possible positions
-            .filter(|(x, y)| !self.snake.overlap_tail(*x, *y)) // don't intersect snake
-            .choose(&mut rng);
-
-        if let Some((new_x, new_y)) = choice {
-            self.food.set_position(new_x, new_y);
-        }
+    /// Seconds per tick: the speed curve at the current score, shortened or
+    /// stretched by the speed food eaten so far.
+    fn move_period(&self) -> f64 {
+        let curve = self.speed_curve.unwrap_or_else(|| self.world.difficulty().curve());
+        let (points, speed_level) = match &self.arena {
+            Some(arena) => (arena.top_points(), arena.speed_level()),
+            None => (self.world.score().points(), self.world.speed_level()),
+        };
+        curve.period(points) * SPEED_FACTOR.powi(speed_level)
     }
 
-    fn update_snake(&mut self, direction: Option<Direction>) {
-        if self.check_if_snake_alive(direction) {
-            self.snake.move_forward(direction);
-            self.check_eating();
-        } else {
-            self.state = GameState::GameOver {
-                elapsed: 0.0,
-                final_score: self.snake.len(),
+    fn on_eat(&mut self) {
+        let current_score = self.world.score().points();
+        if current_score > self.high_score {
+            self.high_score = current_score;
+            let saved = match &self.high_score_path {
+                Some(path) => persistence::try_save_high_score(path, &self.high_score_bucket(), self.high_score),
+                None => Ok(()),
             };
-            if let Some(ref player) = self.sound_player {
-                player.play_death();
+            match saved {
+                Ok(()) => self.notify(format!("New High Score: {}", self.high_score)),
+                Err(e) => self.notify(format!("Failed to save high score: {}", e)),
             }
         }
-        self.waiting_time = 0.0;
     }
 
     pub(crate) fn restart(&mut self) {
-        self.snake = Snake::new(2, 2);
-        self.waiting_time = 0.0;
-        self.food = Food::new(6, 4);
-        self.state = GameState::Playing;
+        match self.arena.as_mut() {
+            Some(arena) => arena.restart(),
+            None => {
+                self.world.restart();
+                self.begin_recording();
+            }
+        }
+        for pilot in &mut self.pilots {
+            pilot.controller().reset();
+        }
+        self.scheduler.reset();
+        self.restart_timer = 0.0;
         if let Some(ref player) = self.sound_player {
             player.play_start();
         }
@@ -491,51 +1653,80 @@ impl Game {
 
     #[cfg(feature = "spectator")]
     pub fn game_snapshot(&self) -> GameSnapshot {
+        if let Some(arena) = &self.arena {
+            let (first, rivals) = arena.players().split_first().expect("a match has players");
+            return GameSnapshot {
+                width: arena.grid().width,
+                height: arena.grid().height,
+                snake: first.snake().body_positions(),
+                rivals: rivals.iter().map(|rival| rival.snake().body_positions()).collect(),
+                foods: food_snapshots(arena.foods()),
+                score: first.score().points(),
+                length: first.snake().len(),
+                state: state_name(arena.state()).to_string(),
+                tick: arena.tick(),
+                seed: arena.seed(),
+                mode: arena.mode().name().to_string(),
+                difficulty: arena.difficulty().name().to_string(),
+                move_period: self.move_period(),
+                walls: arena.level().walls.clone(),
+            };
+        }
+
         GameSnapshot {
-            width: self.grid.width,
-            height: self.grid.height,
-            snake: self.snake.body_positions(),
-            food: self.food.position(),
-            score: self.snake.len(),
-            state: state_name(&self.state).to_string(),
-            tick: self.tick_count,
+            width: self.world.grid().width,
+            height: self.world.grid().height,
+            snake: self.world.snake().body_positions(),
+            rivals: Vec::new(),
+            foods: food_snapshots(self.world.foods()),
+            score: self.world.score().points(),
+            length: self.world.snake().len(),
+            state: state_name(self.world.state()).to_string(),
+            tick: self.world.tick(),
+            seed: self.world.seed(),
+            mode: self.world.mode().name().to_string(),
+            difficulty: self.world.difficulty().name().to_string(),
+            move_period: self.move_period(),
+            walls: self.world.level().walls.clone(),
         }
     }
 
     #[cfg(test)]
     pub(crate) fn is_game_over(&self) -> bool {
-        matches!(self.state, GameState::GameOver { .. })
+        matches!(self.world.state(), GameState::GameOver { .. })
     }
 
     #[cfg(test)]
     pub(crate) fn is_paused(&self) -> bool {
-        matches!(self.state, GameState::Paused)
+        matches!(self.world.state(), GameState::Paused)
     }
 
     #[cfg(test)]
     pub(crate) fn is_menu(&self) -> bool {
-        matches!(self.state, GameState::Menu)
-    }
-
-    #[cfg(test)]
-    pub(crate) fn food_position(&self) -> Option<(i32, i32)> {
-        self.food.position()
+        matches!(self.world.state(), GameState::Menu)
     }
 
     #[cfg(test)]
     pub(crate) fn snake_len(&self) -> usize {
-        self.snake.len()
+        self.world.snake().len()
     }
 
     #[cfg(test)]
     pub(crate) fn snake_head_position(&self) -> (i32, i32) {
-        self.snake.head_position()
+        self.world.snake().head_position()
     }
+}
 
-    #[cfg(test)]
-    pub(crate) fn set_food_position(&mut self, x: i32, y: i32) {
-        self.food.set_position(x, y);
-    }
+#[cfg(feature = "spectator")]
+fn food_snapshots(foods: &[Food]) -> Vec<FoodSnapshot> {
+    foods
+        .iter()
+        .map(|food| FoodSnapshot {
+            x: food.position().0,
+            y: food.position().1,
+            kind: food.kind().name().to_string(),
+        })
+        .collect()
 }
 
 #[cfg(feature = "spectator")]
@@ -545,6 +1736,7 @@ fn state_name(state: &GameState) -> &'static str {
         GameState::Playing => "Playing",
         GameState::Paused => "Paused",
         GameState::GameOver { .. } => "GameOver",
+        GameState::Victory { .. } => "Victory",
     }
 }
 
@@ -552,17 +1744,21 @@ fn state_name(state: &GameState) -> &'static str {
 mod test {
     use super::*;
     use piston_window::Key;
+    use std::cmp::Ordering;
+
+    /// The normal difficulty's starting period.
+    const MOVING_PERIOD: f64 = 0.3;
 
     // Helper to create a game without sound for tests
     fn test_game(width: i32, height: i32) -> Game {
-        Game::new(width, height, None)
+        Game::new(width, height, 0, None)
     }
 
     #[test]
     fn new_creates_game_with_correct_dimensions() {
         let game = test_game(15, 15);
-        assert_eq!(game.grid.width, 15);
-        assert_eq!(game.grid.height, 15);
+        assert_eq!(game.world.grid().width, 15);
+        assert_eq!(game.world.grid().height, 15);
     }
 
     #[test]
@@ -583,12 +1779,6 @@ mod test {
         assert!(game.is_menu());
     }
 
-    #[test]
-    fn new_game_has_food() {
-        let game = test_game(15, 15);
-        assert!(game.food_position().is_some());
-    }
-
     #[test]
     fn new_game_snake_has_initial_length() {
         let game = test_game(15, 15);
@@ -615,6 +1805,7 @@ mod test {
         let initial_pos = game.snake_head_position();
 
         game.key_pressed(Key::Down);
+        game.update(MOVING_PERIOD);
         let new_pos = game.snake_head_position();
 
         assert_eq!(new_pos.1, initial_pos.1 + 1); // moved down
@@ -627,6 +1818,7 @@ mod test {
         let initial_pos = game.snake_head_position();
 
         game.key_pressed(Key::S); // down
+        game.update(MOVING_PERIOD);
         let new_pos = game.snake_head_position();
 
         assert_eq!(new_pos.1, initial_pos.1 + 1);
@@ -640,10 +1832,11 @@ mod test {
         let initial_pos = game.snake_head_position();
 
         game.key_pressed(Key::Left);
+        game.update(MOVING_PERIOD);
         let new_pos = game.snake_head_position();
 
-        // Should still move right (or not move at all), not left
-        assert!(new_pos.0 >= initial_pos.0);
+        // Should still move right, not left
+        assert_eq!(new_pos, (initial_pos.0 + 1, initial_pos.1));
     }
 
     #[test]
@@ -657,6 +1850,37 @@ mod test {
         let pos_after = game.snake_head_position();
 
         assert_eq!(pos_before, pos_after);
+
+        game.key_pressed(Key::Space); // resume
+        game.update(MOVING_PERIOD);
+        assert_eq!(game.snake_head_position(), (pos_before.0 + 1, pos_before.1));
+    }
+
+    #[test]
+    fn key_press_does_not_move_snake_immediately() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::Return); // start from menu
+        let pos_before = game.snake_head_position();
+
+        game.key_pressed(Key::Down);
+        game.key_pressed(Key::X);
+
+        assert_eq!(game.snake_head_position(), pos_before);
+    }
+
+    #[test]
+    fn quick_turns_are_applied_on_consecutive_ticks() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::Return); // start from menu
+        let (x, y) = game.snake_head_position();
+
+        // Both turns land within one period: the snake must not reverse into itself
+        game.key_pressed(Key::Down);
+        game.key_pressed(Key::Left);
+        game.update(MOVING_PERIOD * 2.0 + 0.01);
+
+        assert!(!game.is_game_over());
+        assert_eq!(game.snake_head_position(), (x - 1, y + 1));
     }
 
     #[test]
@@ -672,6 +1896,32 @@ mod test {
         assert_ne!(initial_pos, new_pos);
     }
 
+    #[test]
+    fn long_frame_runs_several_moves() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::Return); // start from menu
+        let (x, y) = game.snake_head_position();
+
+        game.update(MOVING_PERIOD * 3.0 + 0.01);
+
+        assert_eq!(game.snake_head_position(), (x + 3, y));
+        assert_eq!(game.world.tick(), 3);
+    }
+
+    #[test]
+    fn short_frames_accumulate_into_one_move() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::Return); // start from menu
+        let (x, y) = game.snake_head_position();
+
+        for _ in 0..4 {
+            game.update(MOVING_PERIOD / 4.0 + 0.001);
+        }
+
+        assert_eq!(game.snake_head_position(), (x + 1, y));
+        assert_eq!(game.frame_count, 4);
+    }
+
     #[test]
     fn update_does_not_move_when_paused() {
         let mut game = test_game(15, 15);
@@ -720,16 +1970,6 @@ mod test {
         assert!(game.is_game_over());
     }
 
-    #[test]
-    fn check_if_snake_alive_returns_false_at_wall() {
-        let game = test_game(15, 15);
-        // Snake head starts at (4, 2), moving right toward wall at x=14
-        // At x=13, next move would hit wall at x=14 which is width-1
-        // Actually the boundary check is next_x < self.grid.width - 1
-        // So if next_x = 14 and width = 15, 14 < 14 is false
-        assert!(game.check_if_snake_alive(None)); // should be alive initially
-    }
-
     #[test]
     fn eating_food_grows_snake() {
         let mut game = test_game(15, 15);
@@ -737,81 +1977,492 @@ mod test {
         let initial_len = game.snake_len();
 
         // Place food directly in front of snake (snake head at (4,2), moving right)
-        game.set_food_position(5, 2);
+        game.world.set_food_position(5, 2);
         game.update(0.35); // move onto food
 
         assert_eq!(game.snake_len(), initial_len + 1);
     }
 
     #[test]
-    fn eating_food_sets_food_exists_to_false() {
+    fn speed_food_shortens_the_moving_period() {
         let mut game = test_game(15, 15);
         game.key_pressed(Key::Return); // start from menu
+        game.world.set_food_position(10, 10);
+        game.world.place_food(FoodKind::SpeedUp, 5, 2);
+        game.update(0.35); // move onto food
+        game.update(0.0);
 
-        // Place food in front of snake (snake head at (4,2), moving right)
-        game.set_food_position(5, 2);
-        assert!(game.food.exists); // food exists before eating
+        assert!(game.scheduler.period() < MOVING_PERIOD);
+    }
 
-        game.update(0.35); // move onto food
+    #[test]
+    fn f5_saves_and_l_resumes_paused() {
+        let path = "test_game_quicksave.txt";
+        let _ = std::fs::remove_file(path);
 
-        // Snake should have grown (proving food was eaten)
-        assert_eq!(game.snake_len(), 4);
+        let mut game = test_game(15, 15);
+        game.save_path = PathBuf::from(path);
+        game.key_pressed(Key::Return); // start from menu
+        game.update(MOVING_PERIOD);
+        let saved_pos = game.snake_head_position();
+        game.key_pressed(Key::F5);
 
-        // food_exists should now be false (food was consumed)
-        // Note: add_food is called on NEXT update cycle, not immediately
-        assert!(!game.food.exists);
+        let mut resumed = test_game(15, 15);
+        resumed.save_path = PathBuf::from(path);
+        resumed.key_pressed(Key::L);
 
-        // Next update should spawn new food
-        game.update(0.35);
-        assert!(game.food.exists);
+        assert!(resumed.is_paused());
+        assert_eq!(resumed.snake_head_position(), saved_pos);
+        let _ = std::fs::remove_file(path);
     }
 
     #[test]
-    fn add_food_places_food_in_bounds() {
+    fn save_is_reported_in_the_hud_for_a_while() {
+        let path = "test_game_save_message.txt";
+        let _ = std::fs::remove_file(path);
+
+        let mut game = test_game(15, 15);
+        game.save_path = PathBuf::from(path);
+        game.key_pressed(Key::Return);
+        game.key_pressed(Key::F5);
+        let status = game.hud().left_column().pop();
+        assert_eq!(status, Some(format!("Game saved to {}", path)));
+
+        game.key_pressed(Key::Space);
+        game.update(MESSAGE_TIME);
+        assert_eq!(game.hud().left_column().len(), 2);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn recorded_game_plays_back_identically() {
+        let path = "test_game_replay.txt";
+        let _ = std::fs::remove_file(path);
+
         let mut game = test_game(15, 15);
+        game.record_to(PathBuf::from(path));
         game.key_pressed(Key::Return); // start from menu
-        game.food.exists = false;
-        game.add_food();
+        game.key_pressed(Key::Down);
+        game.update(MOVING_PERIOD * 2.0);
+        game.key_pressed(Key::Right);
+        while !game.is_game_over() {
+            game.update(MOVING_PERIOD);
+        }
+
+        let replay = replay::load_from(Path::new(path)).unwrap();
+        let mut watcher = test_game(15, 15);
+        watcher.play_replay(replay);
+        while !watcher.is_game_over() {
+            watcher.update(MOVING_PERIOD);
+        }
+
+        assert_eq!(watcher.world.to_save(), game.world.to_save());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn games_that_eat_replay_to_the_same_board() {
+        let path = "test_game_replay_eating.txt";
+        for seed in 0..20 {
+            let _ = std::fs::remove_file(path);
+            let mut game = Game::new(15, 15, seed, None);
+            game.record_to(PathBuf::from(path));
+            game.key_pressed(Key::Return); // start from menu
+            // Head for the first food until the snake runs into something.
+            while !game.is_game_over() {
+                let (x, y) = game.snake_head_position();
+                let (fx, fy) = game.world.foods()[0].position();
+                let key = match (fx.cmp(&x), fy.cmp(&y)) {
+                    (Ordering::Less, _) => Key::Left,
+                    (Ordering::Greater, _) => Key::Right,
+                    (_, Ordering::Less) => Key::Up,
+                    _ => Key::Down,
+                };
+                game.key_pressed(key);
+                game.update(MOVING_PERIOD);
+            }
+            assert!(game.world.score().points() > 0);
+
+            let mut player = ReplayPlayer::new(replay::load_from(Path::new(path)).unwrap());
+            let mut world = player.world();
+            while player.step(&mut world).is_some() {}
+            assert_eq!(world.to_save(), game.world.to_save(), "seed {}", seed);
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn paused_playback_steps_one_tick_at_a_time() {
+        let mut replay = Replay::start(&World::new(15, 15, 0));
+        replay.record(None);
+        replay.record(Some(Direction::Down));
+
+        let mut game = test_game(15, 15);
+        game.play_replay(replay);
+        game.key_pressed(Key::Space); // pause playback
+        game.update(MOVING_PERIOD * 4.0);
+        assert_eq!(game.snake_head_position(), (4, 2));
+
+        game.key_pressed(Key::N);
+        assert_eq!(game.snake_head_position(), (5, 2));
+        game.key_pressed(Key::N);
+        assert_eq!(game.snake_head_position(), (5, 3));
+    }
+
+    #[test]
+    fn fast_forward_runs_more_ticks_per_update() {
+        let mut replay = Replay::start(&World::new(15, 15, 0));
+        for _ in 0..4 {
+            replay.record(None);
+        }
+
+        let mut game = test_game(15, 15);
+        game.play_replay(replay);
+        game.key_pressed(Key::F); // x2
+        game.key_pressed(Key::F); // x4
+        game.update(MOVING_PERIOD + 0.01);
 
-        let (x, y) = game.food_position().unwrap();
-        assert!(x > 0 && x < 14);
-        assert!(y > 0 && y < 14);
+        assert_eq!(game.snake_head_position(), (8, 2));
     }
 
     #[test]
-    fn restart_resets_game_state() {
+    fn m_toggles_board_mode_in_menu_only() {
         let mut game = test_game(15, 15);
+        game.key_pressed(Key::M);
+        assert_eq!(game.world.mode(), BoardMode::Wrap);
+
         game.key_pressed(Key::Return); // start from menu
+        game.key_pressed(Key::M);
+        assert_eq!(game.world.mode(), BoardMode::Wrap);
+    }
 
-        // Mess up the game state
-        game.state = GameState::GameOver {
-            elapsed: 0.0,
-            final_score: 0,
-        };
-        game.food.exists = false;
+    #[test]
+    fn wrap_mode_snake_survives_the_top_edge() {
+        let mut game = test_game(15, 15);
+        game.set_mode(BoardMode::Wrap);
+        game.key_pressed(Key::Return); // start from menu
+        game.key_pressed(Key::Up);
 
-        game.restart();
+        for _ in 0..20 {
+            game.update(MOVING_PERIOD);
+        }
 
         assert!(!game.is_game_over());
-        assert!(game.food_position().is_some());
-        assert_eq!(game.snake_len(), 3);
     }
 
     #[test]
     fn game_restarts_after_delay_when_game_over() {
         let mut game = test_game(15, 15);
-        game.state = GameState::GameOver {
-            elapsed: 0.0,
-            final_score: 0,
-        };
-        game.waiting_time = 0.0;
+        game.key_pressed(Key::Return); // start from menu
+        game.key_pressed(Key::Up);
+        while !game.is_game_over() {
+            game.update(0.35);
+        }
 
         // Update with less than RESTART_TIME
         game.update(2.0);
         assert!(game.is_game_over()); // still game over
 
         // Update past RESTART_TIME (3.0)
-        game.update(2.0); // total waiting_time now > 3.0
+        game.update(2.0); // total restart_timer now > 3.0
         assert!(!game.is_game_over()); // should restart
     }
+
+    #[test]
+    fn filling_the_board_wins_and_restarts() {
+        let mut game = test_game(15, 15);
+        game.load_level(Level::parse("direction right\nmap\n######\n#..@*#\n######\n").unwrap());
+        game.key_pressed(Key::Return); // start from menu
+        game.update(MOVING_PERIOD);
+        assert!(matches!(game.world.state(), GameState::Victory { .. }));
+        assert_eq!(game.high_score, FoodKind::Normal.points());
+
+        game.update(RESTART_TIME + 0.1);
+        assert!(matches!(game.world.state(), GameState::Playing));
+    }
+
+    #[test]
+    fn load_level_replaces_board_from_menu() {
+        let mut game = test_game(15, 15);
+        game.set_mode(BoardMode::Wrap);
+        game.load_level(Level::parse("map\n#########\n#..@..#.#\n#.......#\n#########\n").unwrap());
+
+        assert_eq!(game.board_size(), (9, 4));
+        assert!(game.world.is_wall(6, 1));
+        assert_eq!(game.world.mode(), BoardMode::Wrap);
+    }
+
+    #[test]
+    fn load_level_is_ignored_while_playing() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::Return); // start from menu
+        game.load_level(Level::empty(20, 20));
+        assert_eq!(game.board_size(), (15, 15));
+    }
+
+    #[test]
+    fn d_cycles_difficulty_only_in_menu() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::D);
+        assert_eq!(game.world.difficulty(), Difficulty::Hard);
+
+        game.key_pressed(Key::Return); // start from menu
+        game.key_pressed(Key::D);
+        assert_eq!(game.world.difficulty(), Difficulty::Hard);
+    }
+
+    #[test]
+    fn difficulty_sets_the_moving_period() {
+        let mut game = test_game(15, 15);
+        game.set_difficulty(Difficulty::Insane);
+        game.update(0.0);
+        assert_eq!(game.scheduler.period(), Difficulty::Insane.curve().start_period);
+    }
+
+    #[test]
+    fn custom_speed_curve_overrides_difficulty() {
+        let mut game = test_game(15, 15);
+        game.set_speed_curve(Some(SpeedCurve::constant(0.5)));
+        game.update(0.0);
+        assert_eq!(game.scheduler.period(), 0.5);
+    }
+
+    fn head(game: &Game, player: usize) -> (i32, i32) {
+        game.arena.as_ref().unwrap().players()[player].snake().head_position()
+    }
+
+    #[test]
+    fn p_toggles_two_players_in_menu() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::P);
+        assert_eq!(game.players(), 2);
+        game.key_pressed(Key::P);
+        assert_eq!(game.players(), 1);
+    }
+
+    #[test]
+    fn two_players_need_room_for_both_snakes() {
+        let mut game = test_game(7, 5);
+        assert!(!game.set_players(2));
+        assert_eq!(game.players(), 1);
+    }
+
+    #[test]
+    fn wasd_steers_player_one_and_arrows_player_two() {
+        let mut game = test_game(15, 15);
+        game.set_players(2);
+        game.key_pressed(Key::Return);
+        let (one, two) = (head(&game, 0), head(&game, 1));
+
+        game.key_pressed(Key::S);
+        game.key_pressed(Key::Up);
+        game.update(MOVING_PERIOD);
+
+        assert_eq!(head(&game, 0), (one.0, one.1 + 1));
+        assert_eq!(head(&game, 1), (two.0, two.1 - 1));
+    }
+
+    #[test]
+    fn match_ends_with_a_winner_and_restarts() {
+        let mut game = test_game(15, 15);
+        game.set_players(2);
+        game.key_pressed(Key::Return);
+        game.key_pressed(Key::W);
+        game.update(MOVING_PERIOD);
+        game.update(MOVING_PERIOD);
+
+        let arena = game.arena.as_ref().unwrap();
+        assert_eq!(arena.result(), Some(MatchResult::Winner(1)));
+        assert!(matches!(arena.state(), GameState::GameOver { .. }));
+
+        game.update(RESTART_TIME + 0.1);
+        let arena = game.arena.as_ref().unwrap();
+        assert_eq!(*arena.state(), GameState::Playing);
+        assert!(arena.players().iter().all(|player| player.is_alive()));
+    }
+
+    /// Always asks for the same turn.
+    struct Steady(Direction);
+
+    impl Controller for Steady {
+        fn name(&self) -> &str {
+            "steady"
+        }
+
+        fn next_turn(&mut self, _view: &snake::controller::BoardView) -> Option<Direction> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn controller_steers_instead_of_the_keyboard() {
+        let mut game = test_game(15, 15);
+        game.set_controller(0, Box::new(Steady(Direction::Down)));
+        game.key_pressed(Key::Return);
+        let (x, y) = game.snake_head_position();
+
+        game.key_pressed(Key::Up);
+        game.update(MOVING_PERIOD);
+
+        assert_eq!(game.snake_head_position(), (x, y + 1));
+    }
+
+    #[test]
+    fn t_toggles_the_autopilot_during_play() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::T);
+        assert!(matches!(game.pilots[0], Pilot::Human(_)));
+
+        game.key_pressed(Key::Return);
+        game.key_pressed(Key::T);
+        assert!(matches!(game.pilots[0], Pilot::Autopilot(_)));
+        for _ in 0..100 {
+            game.update(MOVING_PERIOD);
+        }
+        assert!(!game.is_game_over());
+
+        game.key_pressed(Key::T);
+        assert!(matches!(game.pilots[0], Pilot::Human(_)));
+    }
+
+    #[test]
+    fn bots_can_take_extra_arena_seats() {
+        let mut game = test_game(15, 15);
+        game.set_controller(2, Box::new(Steady(Direction::Up)));
+        assert!(game.set_players(3));
+        assert_eq!(game.player_label(0), "P1");
+        assert_eq!(game.player_label(2), "P3 steady");
+
+        game.key_pressed(Key::Return);
+        let (x, y) = head(&game, 2);
+        game.update(MOVING_PERIOD);
+        assert_eq!(head(&game, 2), (x, y - 1));
+    }
+
+    #[test]
+    fn menu_changes_carry_over_to_the_match() {
+        let mut game = test_game(15, 15);
+        game.set_players(2);
+        game.key_pressed(Key::M);
+        assert_eq!(game.arena.as_ref().unwrap().mode(), BoardMode::Wrap);
+    }
+
+    #[test]
+    fn keys_rebound_in_the_menu_replace_the_old_ones() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::K);
+        assert_eq!(game.overlay().title(), Some("Keys".to_string()));
+        game.key_pressed(Key::Down); // select "down"
+        game.key_pressed(Key::Return);
+        game.key_pressed(Key::J);
+        game.key_pressed(Key::Escape);
+        assert_eq!(game.overlay().title(), Some("SNAKE".to_string()));
+        assert!(!game.wants_to_quit());
+
+        game.key_pressed(Key::Return);
+        let start = game.snake_head_position();
+        game.key_pressed(Key::S);
+        game.update(MOVING_PERIOD);
+        assert_eq!(game.snake_head_position(), (start.0 + 1, start.1));
+        game.key_pressed(Key::J);
+        game.update(MOVING_PERIOD);
+        assert_eq!(game.snake_head_position(), (start.0 + 1, start.1 + 1));
+    }
+
+    #[test]
+    fn escape_asks_to_quit() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::Return);
+        assert!(!game.wants_to_quit());
+        game.key_pressed(Key::Escape);
+        assert!(game.wants_to_quit());
+    }
+
+    #[test]
+    fn r_restarts_right_away() {
+        let mut game = test_game(15, 15);
+        game.key_pressed(Key::Return);
+        game.key_pressed(Key::Up);
+        while !game.is_game_over() {
+            game.update(0.35);
+        }
+        game.key_pressed(Key::R);
+        assert!(!game.is_game_over());
+    }
+
+    #[test]
+    fn one_button_starts_then_pauses() {
+        let mut game = test_game(15, 15);
+        let start_button = [Action::Start, Action::Pause];
+        game.perform(&start_button);
+        assert!(!game.is_menu() && !game.is_paused());
+        game.perform(&start_button);
+        assert!(game.is_paused());
+    }
+
+    /// A game in the menu with the main page listing no resume entry.
+    fn menu_game() -> Game {
+        let mut game = test_game(15, 15);
+        game.save_available = false;
+        game
+    }
+
+    #[test]
+    fn menu_items_change_with_left_and_right() {
+        let mut game = menu_game();
+        game.key_pressed(Key::Down);
+        game.key_pressed(Key::Down); // difficulty
+        game.key_pressed(Key::Right);
+        assert_eq!(game.world.difficulty(), Difficulty::Hard);
+        game.key_pressed(Key::Left);
+        game.key_pressed(Key::Left);
+        assert_eq!(game.world.difficulty(), Difficulty::Easy);
+        assert!(game.menu_lines().contains(&"> Difficulty: easy".to_string()));
+        assert!(game.is_menu());
+    }
+
+    #[test]
+    fn board_size_steps_through_the_presets() {
+        let mut game = menu_game();
+        for _ in 0..3 {
+            game.key_pressed(Key::Down);
+        }
+        game.key_pressed(Key::Return);
+        assert_eq!(game.board_size(), (20, 15));
+        game.key_pressed(Key::Left);
+        game.key_pressed(Key::Left);
+        assert_eq!(game.board_size(), (10, 10));
+        assert_eq!(next_board_size((12, 12), 1), (15, 15));
+        assert_eq!(next_board_size((12, 12), -1), (10, 10));
+    }
+
+    #[test]
+    fn escape_leaves_menu_pages_before_quitting() {
+        let mut game = menu_game();
+        game.key_pressed(Key::Up);
+        game.key_pressed(Key::Up);
+        game.key_pressed(Key::Up); // options
+        game.key_pressed(Key::Return);
+        assert_eq!(game.overlay().title(), Some("Options".to_string()));
+        game.key_pressed(Key::Escape);
+        assert_eq!(game.overlay().title(), Some("SNAKE".to_string()));
+        assert!(!game.wants_to_quit());
+        game.key_pressed(Key::Escape);
+        assert!(game.wants_to_quit());
+    }
+
+    #[test]
+    fn menu_settings_are_saved_to_the_config_file() {
+        let path = std::env::temp_dir().join(format!("snake_menu_test_{}.toml", std::process::id()));
+        std::fs::write(&path, "[board]\nlevel = \"levels/pillars.txt\"\n").unwrap();
+        let mut game = menu_game();
+        game.set_config_path(Some(path.clone()));
+        game.key_pressed(Key::M);
+        game.set_board_size(10, 10);
+        game.choose(Item::BoardSize, 1);
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(saved, "[board]\nmode = \"wrap\"\nwidth = 15\nheight = 15\n");
+    }
 }
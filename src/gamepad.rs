@@ -0,0 +1,184 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+use snake::snake::Direction;
+
+use crate::keys::Action;
+
+/// How far a stick has to lean, from 0.0 to 1.0, before it turns the snake.
+pub const DEFAULT_DEADZONE: f64 = 0.4;
+
+/// Turns gamepad input into the same actions keys trigger.
+///
+/// The window backend does not report gamepads, so they are read through
+/// gilrs, polled once a frame. The first controller heard from steers player
+/// one and the second player two. The D-pad turns on press; the left stick
+/// turns once when it leaves the deadzone or swings to another direction,
+/// following whichever axis leans furthest. `A` starts, `Start` starts and
+/// pauses, `B` pauses and `Back` restarts.
+pub struct Gamepad {
+    /// `None` when the platform has no gamepad support.
+    gilrs: Option<Gilrs>,
+    deadzone: f64,
+    /// Controller ids in the order they were first heard from.
+    ids: Vec<usize>,
+    /// Stick position and the direction it last turned, by player.
+    sticks: Vec<Stick>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Stick {
+    x: f64,
+    y: f64,
+    direction: Option<Direction>,
+}
+
+impl Gamepad {
+    /// Opens the gamepad backend, carrying on without gamepads if it fails.
+    pub fn new(deadzone: f64) -> Gamepad {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                eprintln!("Gamepads unavailable: {}", e);
+                None
+            }
+        };
+        Gamepad {
+            gilrs,
+            ..Gamepad::without_backend(deadzone)
+        }
+    }
+
+    fn without_backend(deadzone: f64) -> Gamepad {
+        Gamepad {
+            gilrs: None,
+            deadzone,
+            ids: Vec::new(),
+            sticks: Vec::new(),
+        }
+    }
+
+    /// The actions of the next gamepad event that triggers any, or `None`
+    /// once every event so far has been read. Each event is acted on by
+    /// itself, so two controllers turning in the same frame both count.
+    pub fn next_actions(&mut self) -> Option<Vec<Action>> {
+        loop {
+            let event = self.gilrs.as_mut()?.next_event()?;
+            let id = usize::from(event.id);
+            let actions = match event.event {
+                EventType::ButtonPressed(button, _) => self.button(id, button),
+                EventType::AxisChanged(axis, position, _) => self.axis(id, axis, f64::from(position)),
+                _ => Vec::new(),
+            };
+            if !actions.is_empty() {
+                return Some(actions);
+            }
+        }
+    }
+
+    fn button(&mut self, id: usize, button: Button) -> Vec<Action> {
+        let player = self.player(id);
+        let direction = match button {
+            Button::South => return vec![Action::Start],
+            Button::East => return vec![Action::Pause],
+            Button::Select => return vec![Action::Restart],
+            Button::Start => return vec![Action::Start, Action::Pause],
+            Button::DPadUp => Direction::Up,
+            Button::DPadDown => Direction::Down,
+            Button::DPadLeft => Direction::Left,
+            Button::DPadRight => Direction::Right,
+            _ => return Vec::new(),
+        };
+        turn(player, direction).into_iter().collect()
+    }
+
+    /// gilrs counts the stick's Y axis upwards.
+    fn axis(&mut self, id: usize, axis: Axis, position: f64) -> Vec<Action> {
+        if axis != Axis::LeftStickX && axis != Axis::LeftStickY {
+            return Vec::new();
+        }
+        let player = self.player(id);
+        let deadzone = self.deadzone;
+        let stick = &mut self.sticks[player];
+        if axis == Axis::LeftStickX {
+            stick.x = position;
+        } else {
+            stick.y = position;
+        }
+
+        let direction = if stick.x.abs().max(stick.y.abs()) < deadzone {
+            None
+        } else if stick.x.abs() > stick.y.abs() {
+            Some(if stick.x < 0.0 { Direction::Left } else { Direction::Right })
+        } else {
+            Some(if stick.y > 0.0 { Direction::Up } else { Direction::Down })
+        };
+        if direction == stick.direction {
+            return Vec::new();
+        }
+        stick.direction = direction;
+        direction.and_then(|direction| turn(player, direction)).into_iter().collect()
+    }
+
+    fn player(&mut self, id: usize) -> usize {
+        match self.ids.iter().position(|known| *known == id) {
+            Some(player) => player,
+            None => {
+                self.ids.push(id);
+                self.sticks.push(Stick::default());
+                self.ids.len() - 1
+            }
+        }
+    }
+}
+
+/// The turn action for `player`, if there is one for that seat.
+fn turn(player: usize, direction: Direction) -> Option<Action> {
+    Action::ALL.into_iter().find(|action| action.turn() == Some((player, direction)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stick_turns_once_outside_the_deadzone() {
+        let mut gamepad = Gamepad::without_backend(DEFAULT_DEADZONE);
+        assert_eq!(gamepad.axis(0, Axis::LeftStickX, 0.2), vec![]);
+        assert_eq!(gamepad.axis(0, Axis::LeftStickX, 0.8), vec![Action::TurnRight]);
+        assert_eq!(gamepad.axis(0, Axis::LeftStickX, 0.9), vec![]);
+        assert_eq!(gamepad.axis(0, Axis::LeftStickX, 0.1), vec![]);
+        assert_eq!(gamepad.axis(0, Axis::LeftStickX, 0.9), vec![Action::TurnRight]);
+    }
+
+    #[test]
+    fn stick_follows_the_dominant_axis() {
+        let mut gamepad = Gamepad::without_backend(DEFAULT_DEADZONE);
+        assert_eq!(gamepad.axis(0, Axis::LeftStickX, -0.6), vec![Action::TurnLeft]);
+        assert_eq!(gamepad.axis(0, Axis::LeftStickY, -0.5), vec![]);
+        assert_eq!(gamepad.axis(0, Axis::LeftStickY, -0.7), vec![Action::TurnDown]);
+        assert_eq!(gamepad.axis(0, Axis::LeftStickY, 0.9), vec![Action::TurnUp]);
+    }
+
+    #[test]
+    fn second_controller_steers_player_two() {
+        let mut gamepad = Gamepad::without_backend(DEFAULT_DEADZONE);
+        assert_eq!(gamepad.button(3, Button::DPadUp), vec![Action::TurnUp]);
+        assert_eq!(gamepad.button(7, Button::DPadLeft), vec![Action::P2TurnLeft]);
+        assert_eq!(gamepad.axis(7, Axis::LeftStickY, -0.9), vec![Action::P2TurnDown]);
+    }
+
+    #[test]
+    fn buttons_start_and_pause() {
+        let mut gamepad = Gamepad::without_backend(DEFAULT_DEADZONE);
+        assert_eq!(gamepad.button(0, Button::Start), vec![Action::Start, Action::Pause]);
+        assert_eq!(gamepad.button(0, Button::South), vec![Action::Start]);
+        assert_eq!(gamepad.button(0, Button::Select), vec![Action::Restart]);
+        assert_eq!(gamepad.button(0, Button::North), vec![]);
+    }
+
+    #[test]
+    fn other_axes_do_not_turn() {
+        let mut gamepad = Gamepad::without_backend(DEFAULT_DEADZONE);
+        assert_eq!(gamepad.axis(0, Axis::RightStickX, 0.9), vec![]);
+        assert_eq!(gamepad.axis(0, Axis::LeftZ, 1.0), vec![]);
+    }
+}
@@ -0,0 +1,426 @@
+use crate::controller::{turn_left, turn_right, BoardView};
+use crate::food::Food;
+use crate::level::Level;
+use crate::rng::GameRng;
+use crate::sim::{self, Ending};
+use crate::snake::Direction;
+use crate::world::{BoardMode, GameState, StepOutcome, World};
+
+/// A move relative to the snake's heading, so the same three actions are
+/// valid in every state.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    Straight,
+    Left,
+    Right,
+}
+
+impl Action {
+    pub const ALL: [Action; 3] = [Action::Straight, Action::Left, Action::Right];
+
+    /// The action numbered `index` in [`Action::ALL`], for agents that pick
+    /// actions by index.
+    pub fn from_index(index: usize) -> Option<Action> {
+        Action::ALL.get(index).copied()
+    }
+
+    pub fn index(&self) -> usize {
+        match self {
+            Action::Straight => 0,
+            Action::Left => 1,
+            Action::Right => 2,
+        }
+    }
+
+    /// The turn this action makes from `heading`, `None` to go straight.
+    pub fn turn(&self, heading: Direction) -> Option<Direction> {
+        match self {
+            Action::Straight => None,
+            Action::Left => Some(turn_left(heading)),
+            Action::Right => Some(turn_right(heading)),
+        }
+    }
+}
+
+/// How the board is encoded for the agent.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Encoding {
+    /// One `height` x `width` plane per [`GRID_CHANNELS`] entry, row by
+    /// row, with 1.0 where the plane's content is.
+    Grid,
+    /// [`FEATURE_COUNT`] numbers describing the head's surroundings.
+    #[default]
+    Features,
+}
+
+/// The planes of a [`Encoding::Grid`] observation, in order.
+pub const GRID_CHANNELS: [&str; 4] = ["head", "body", "food", "wall"];
+
+/// The entries of an [`Encoding::Features`] observation: whether moving
+/// straight, left or right dies at once, the heading as up/down/left/right,
+/// where the nearest food lies as up/down/left/right of the head, and the
+/// snake's length as a share of the board.
+pub const FEATURE_COUNT: usize = 12;
+
+/// What the agent sees of the board, flattened into `data` in row-major
+/// order of `shape`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+/// The reward for each thing that can happen on a step; they add up.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rewards {
+    /// For eating any food.
+    pub food: f32,
+    /// For dying.
+    pub death: f32,
+    /// For filling the board.
+    pub victory: f32,
+    /// For every step taken, usually zero or a small penalty.
+    pub step: f32,
+    /// For each cell the head gets closer to the nearest food, and taken
+    /// away for each cell it moves further.
+    pub approach: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards {
+            food: 1.0,
+            death: -1.0,
+            victory: 10.0,
+            step: 0.0,
+            approach: 0.0,
+        }
+    }
+}
+
+/// Everything about an environment that stays the same across episodes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvConfig {
+    /// The board to play on; an empty 15 x 15 one by default.
+    pub level: Level,
+    pub mode: BoardMode,
+    pub encoding: Encoding,
+    pub rewards: Rewards,
+    /// Episodes still running after this many ticks end as truncated.
+    pub max_ticks: u64,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            level: Level::empty(15, 15),
+            mode: BoardMode::Walls,
+            encoding: Encoding::Features,
+            rewards: Rewards::default(),
+            max_ticks: 10_000,
+        }
+    }
+}
+
+/// Details about a step beyond its reward.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepInfo {
+    pub outcome: StepOutcome,
+    pub score: u32,
+    pub length: usize,
+    pub tick: u64,
+    /// How the episode ended, once it has; [`Ending::OutOfTicks`] when it
+    /// was cut off at [`EnvConfig::max_ticks`].
+    pub ending: Option<Ending>,
+}
+
+/// A gym-style environment around the game rules: [`Env::reset`] starts an
+/// episode, [`Env::step`] plays one tick.
+pub struct Env {
+    config: EnvConfig,
+    world: World,
+}
+
+impl Env {
+    pub fn new(config: EnvConfig) -> Env {
+        let world = World::with_level(config.level.clone(), GameRng::new(0));
+        let mut env = Env { config, world };
+        env.reset(0);
+        env
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    /// The game being played, for inspection or rendering.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Starts a new episode whose food follows `seed`.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.world = World::with_level(self.config.level.clone(), GameRng::new(seed));
+        self.world.set_mode(self.config.mode);
+        self.world.start();
+        self.observe()
+    }
+
+    /// Plays `action` for one tick and returns what the agent sees
+    /// afterwards, the reward, whether the episode is over and the details.
+    /// Once it is over further steps change nothing and earn nothing.
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool, StepInfo) {
+        if self.is_done() {
+            let info = self.info(StepOutcome::Idle);
+            return (self.observe(), 0.0, true, info);
+        }
+
+        let rewards = self.config.rewards;
+        // The step refills the board, so the approach is measured against
+        // the food that was there when the agent chose its move.
+        let foods: Vec<(i32, i32)> = self.world.foods().iter().map(Food::position).collect();
+        let before = self.food_distance(&foods);
+        let turn = action.turn(self.world.snake().head_direction());
+        let outcome = self.world.step_with(turn);
+
+        let mut reward = rewards.step;
+        match outcome {
+            StepOutcome::Ate(_) => reward += rewards.food,
+            StepOutcome::Died => reward += rewards.death,
+            StepOutcome::Won => reward += rewards.food + rewards.victory,
+            StepOutcome::Moved => {
+                if let (Some(before), Some(after)) = (before, self.food_distance(&foods)) {
+                    reward += rewards.approach * (before - after) as f32;
+                }
+            }
+            StepOutcome::Idle => {}
+        }
+
+        let info = self.info(outcome);
+        (self.observe(), reward, self.is_done(), info)
+    }
+
+    /// Whether the episode has ended, by the rules or the tick limit.
+    pub fn is_done(&self) -> bool {
+        *self.world.state() != GameState::Playing || self.world.tick() >= self.config.max_ticks
+    }
+
+    fn info(&self, outcome: StepOutcome) -> StepInfo {
+        let ending = sim::ending(&self.world).or_else(|| self.is_done().then_some(Ending::OutOfTicks));
+        StepInfo {
+            outcome,
+            score: self.world.score().points(),
+            length: self.world.snake().len(),
+            tick: self.world.tick(),
+            ending,
+        }
+    }
+
+    /// The current state in the configured encoding.
+    pub fn observe(&self) -> Observation {
+        match self.config.encoding {
+            Encoding::Grid => grid_observation(&self.world),
+            Encoding::Features => feature_observation(&self.world),
+        }
+    }
+
+    /// Manhattan distance from the head to the nearest food.
+    fn food_distance(&self, foods: &[(i32, i32)]) -> Option<i32> {
+        let (x, y) = self.world.snake().head_position();
+        foods.iter().map(|(fx, fy)| (fx - x).abs() + (fy - y).abs()).min()
+    }
+}
+
+fn grid_observation(world: &World) -> Observation {
+    let grid = world.grid();
+    let (width, height) = (grid.width as usize, grid.height as usize);
+    let plane = width * height;
+    let mut data = vec![0.0; GRID_CHANNELS.len() * plane];
+    let mut set = |channel: usize, (x, y): (i32, i32)| {
+        if x >= 0 && y >= 0 && x < grid.width && y < grid.height {
+            data[channel * plane + y as usize * width + x as usize] = 1.0;
+        }
+    };
+
+    let snake = world.snake();
+    set(0, snake.head_position());
+    for cell in snake.body_positions().into_iter().skip(1) {
+        set(1, cell);
+    }
+    for food in world.foods() {
+        set(2, food.position());
+    }
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            if !grid.is_inside(x, y) || world.is_wall(x, y) {
+                set(3, (x, y));
+            }
+        }
+    }
+    Observation {
+        shape: vec![GRID_CHANNELS.len(), height, width],
+        data,
+    }
+}
+
+fn feature_observation(world: &World) -> Observation {
+    let view: BoardView = world.view();
+    let snake = view.snake();
+    let head = snake.head_position();
+    let heading = snake.head_direction();
+    let flag = |on: bool| if on { 1.0 } else { 0.0 };
+
+    let mut data = Vec::with_capacity(FEATURE_COUNT);
+    for turn in [heading, turn_left(heading), turn_right(heading)] {
+        let (x, y) = view.neighbour(head, turn);
+        data.push(flag(!view.is_free(x, y)));
+    }
+    for direction in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+        data.push(flag(heading == direction));
+    }
+    let food = view
+        .foods()
+        .iter()
+        .map(|food| food.position())
+        .min_by_key(|(x, y)| (x - head.0).abs() + (y - head.1).abs());
+    match food {
+        Some((x, y)) => {
+            data.extend([flag(y < head.1), flag(y > head.1), flag(x < head.0), flag(x > head.0)]);
+        }
+        None => data.extend([0.0; 4]),
+    }
+    data.push(snake.len() as f32 / world.playable_cells().max(1) as f32);
+
+    Observation {
+        shape: vec![FEATURE_COUNT],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::food::FoodKind;
+
+    fn env(encoding: Encoding) -> Env {
+        Env::new(EnvConfig {
+            encoding,
+            ..EnvConfig::default()
+        })
+    }
+
+    /// Puts the nearest food at (x, y) and fills the other slots far away in
+    /// the bottom corners, so no new food turns up on the next step.
+    fn fill_food(env: &mut Env, (x, y): (i32, i32)) {
+        env.world.set_food_position(x, y);
+        env.world.place_food(FoodKind::Normal, 13, 13);
+        env.world.place_food(FoodKind::Normal, 1, 13);
+    }
+
+    #[test]
+    fn actions_turn_relative_to_the_heading() {
+        assert_eq!(Action::Straight.turn(Direction::Up), None);
+        assert_eq!(Action::Left.turn(Direction::Up), Some(Direction::Left));
+        assert_eq!(Action::Right.turn(Direction::Up), Some(Direction::Right));
+        for action in Action::ALL {
+            assert_eq!(Action::from_index(action.index()), Some(action));
+        }
+        assert_eq!(Action::from_index(3), None);
+    }
+
+    #[test]
+    fn reset_with_a_seed_repeats_the_episode() {
+        let mut env = env(Encoding::Grid);
+        let run = |env: &mut Env| {
+            let mut observations = vec![env.reset(9)];
+            for action in [Action::Straight, Action::Right, Action::Straight, Action::Left] {
+                observations.push(env.step(action).0);
+            }
+            observations
+        };
+        assert_eq!(run(&mut env), run(&mut env));
+    }
+
+    #[test]
+    fn eating_is_rewarded() {
+        let mut env = env(Encoding::Features);
+        env.world.set_food_position(5, 2);
+        let (_, reward, done, info) = env.step(Action::Straight);
+        assert_eq!(reward, 1.0);
+        assert!(!done);
+        assert_eq!(info.outcome, StepOutcome::Ate(FoodKind::Normal));
+        assert_eq!(info.length, 4);
+    }
+
+    #[test]
+    fn dying_ends_the_episode() {
+        let mut env = env(Encoding::Features);
+        env.world.set_food_position(13, 13);
+        let mut last = None;
+        while !env.is_done() {
+            last = Some(env.step(Action::Straight));
+        }
+        let (_, reward, done, info) = last.unwrap();
+        assert_eq!(reward, -1.0);
+        assert!(done);
+        assert_eq!(info.ending, Some(Ending::Border));
+
+        let (_, reward, done, info) = env.step(Action::Straight);
+        assert_eq!((reward, done, info.outcome), (0.0, true, StepOutcome::Idle));
+    }
+
+    #[test]
+    fn long_episodes_are_truncated() {
+        let mut env = Env::new(EnvConfig {
+            max_ticks: 3,
+            ..EnvConfig::default()
+        });
+        let steps: Vec<bool> = (0..3).map(|_| env.step(Action::Straight).2).collect();
+        assert_eq!(steps, [false, false, true]);
+        assert_eq!(env.step(Action::Straight).3.ending, Some(Ending::OutOfTicks));
+    }
+
+    #[test]
+    fn approach_shaping_rewards_closing_in() {
+        let mut env = Env::new(EnvConfig {
+            rewards: Rewards {
+                approach: 0.1,
+                ..Rewards::default()
+            },
+            ..EnvConfig::default()
+        });
+        fill_food(&mut env, (10, 2));
+        assert_eq!(env.step(Action::Straight).1, 0.1);
+        assert_eq!(env.step(Action::Left).1, -0.1);
+    }
+
+    #[test]
+    fn grid_marks_head_body_food_and_walls() {
+        let mut env = env(Encoding::Grid);
+        env.world.set_food_position(6, 4);
+        let observation = env.observe();
+        assert_eq!(observation.shape, vec![4, 15, 15]);
+        let at = |channel: usize, x: usize, y: usize| observation.data[channel * 225 + y * 15 + x];
+        assert_eq!(at(0, 4, 2), 1.0);
+        assert_eq!(at(1, 3, 2), 1.0);
+        assert_eq!(at(1, 4, 2), 0.0);
+        assert_eq!(at(2, 6, 4), 1.0);
+        assert_eq!(at(3, 0, 7), 1.0);
+        assert_eq!(at(3, 7, 7), 0.0);
+    }
+
+    #[test]
+    fn features_describe_the_surroundings() {
+        // Heading up right below the border, with food down and to the right.
+        let mut env = env(Encoding::Features);
+        fill_food(&mut env, (6, 4));
+        env.step(Action::Left);
+        let observation = env.observe();
+        assert_eq!(observation.shape, vec![FEATURE_COUNT]);
+        assert_eq!(
+            observation.data[..11],
+            [1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0]
+        );
+        assert_eq!(observation.data[11], 3.0 / 169.0);
+    }
+}
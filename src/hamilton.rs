@@ -0,0 +1,295 @@
+use crate::autopilot::Autopilot;
+use crate::controller::{BoardView, Controller};
+use crate::level::Level;
+use crate::snake::Direction;
+use crate::world::Grid;
+
+/// How many free cells a shortcut has to leave between the head and the
+/// tail on top of the snake's length. The cells it skips only open up again
+/// once the tail has passed them, and the snake may grow meanwhile.
+const SHORTCUT_MARGIN: usize = 10;
+
+/// Plays a perfect game: walks a Hamiltonian cycle through every playable
+/// cell, so its tail is always ahead of it and it can fill the whole board.
+///
+/// While the snake is short it cuts across the cycle towards food, but only
+/// when the cut cannot skip over its own body. Boards without a cycle (both
+/// sides odd, or walls inside) are left to the [`Autopilot`].
+#[derive(Default)]
+pub struct HamiltonBot {
+    cycle: Option<Cycle>,
+    /// +1 to walk the cycle forwards, -1 backwards; picked on the first tick.
+    step: Option<isize>,
+    fallback: Autopilot,
+}
+
+impl HamiltonBot {
+    pub fn new() -> HamiltonBot {
+        HamiltonBot::default()
+    }
+
+    /// Picks the way round the cycle that leads away from the neck, keeping
+    /// the previous choice while it still works.
+    fn orientation(&mut self, cycle: &Cycle, view: &BoardView) -> Option<isize> {
+        let snake = view.snake();
+        let head = snake.head_position();
+        let index = cycle.index(head)?;
+        let reverse = snake.head_direction().opposite();
+        let usable = |step: isize| {
+            let next = cycle.cell_at(index, step);
+            view.is_free(next.0, next.1) && view.neighbour(head, reverse) != next
+        };
+        let preferred = self.step.unwrap_or(1);
+        let step = [preferred, -preferred].into_iter().find(|step| usable(*step))?;
+        self.step = Some(step);
+        Some(step)
+    }
+}
+
+impl Controller for HamiltonBot {
+    fn name(&self) -> &str {
+        "hamilton"
+    }
+
+    fn next_turn(&mut self, view: &BoardView) -> Option<Direction> {
+        let grid = view.grid();
+        if self.cycle.as_ref().is_none_or(|cycle| !cycle.fits(grid)) {
+            self.cycle = Cycle::new(grid, |x, y| view.is_wall(x, y));
+        }
+        let Some(cycle) = self.cycle.take() else {
+            return self.fallback.next_turn(view);
+        };
+        let turn = match self.orientation(&cycle, view) {
+            Some(step) => {
+                let head = cycle.index(view.snake().head_position()).expect("heads stay inside");
+                let target = shortcut(&cycle, step, view).unwrap_or_else(|| cycle.cell_at(head, step));
+                direction_to(view, target).filter(|dir| *dir != view.snake().head_direction())
+            }
+            None => self.fallback.next_turn(view),
+        };
+        self.cycle = Some(cycle);
+        turn
+    }
+
+    fn reset(&mut self) {
+        self.cycle = None;
+        self.step = None;
+    }
+}
+
+/// Whether the bot finds a cycle on `level`. Without one it plays like the
+/// [`Autopilot`], which callers may want to point out.
+pub fn has_cycle(level: &Level) -> bool {
+    let grid = Grid::new(level.width, level.height);
+    Cycle::new(&grid, |x, y| level.walls.contains(&(x, y))).is_some()
+}
+
+/// The neighbour of the head to cut across to, if any: the one furthest
+/// along the cycle that neither passes the nearest food nor gets closer to
+/// the tail than [`SHORTCUT_MARGIN`].
+///
+/// Only taken while the body lies in cycle order behind the head and covers
+/// less than half the board, so the cells the cut skips are all free.
+fn shortcut(cycle: &Cycle, step: isize, view: &BoardView) -> Option<(i32, i32)> {
+    let snake = view.snake();
+    let head = snake.head_position();
+    let body = snake.body_positions();
+    let tail = *body.last().expect("a snake has a body");
+    if snake.len() * 2 >= cycle.len() {
+        return None;
+    }
+    let distance = |from, to| cycle.distance(from, to, step);
+    let to_head = distance(tail, head)?;
+    for cell in &body {
+        if distance(tail, *cell)? > to_head {
+            return None;
+        }
+    }
+
+    let to_food = view
+        .foods()
+        .iter()
+        .filter_map(|food| distance(head, food.position()))
+        .min()?;
+    let to_tail = distance(head, tail)?;
+    view.safe_directions()
+        .into_iter()
+        .map(|dir| view.neighbour(head, dir))
+        .filter_map(|cell| Some((cell, distance(head, cell)?)))
+        .filter(|(_, ahead)| {
+            *ahead <= to_food
+                && to_tail.checked_sub(*ahead).is_some_and(|room| room > snake.len() + SHORTCUT_MARGIN)
+        })
+        .max_by_key(|(_, ahead)| *ahead)
+        .map(|(cell, _)| cell)
+}
+
+/// The direction that moves the head onto `target`, one of its neighbours.
+fn direction_to(view: &BoardView, target: (i32, i32)) -> Option<Direction> {
+    let head = view.snake().head_position();
+    [Direction::Up, Direction::Down, Direction::Left, Direction::Right]
+        .into_iter()
+        .find(|dir| view.neighbour(head, *dir) == target)
+}
+
+/// A closed path through every playable cell, each step to a neighbour.
+struct Cycle {
+    width: i32,
+    height: i32,
+    cells: Vec<(i32, i32)>,
+    /// Position of each board cell in `cells`, row by row.
+    order: Vec<Option<usize>>,
+}
+
+impl Cycle {
+    /// Lays out a cycle over the cells inside the border: along the first
+    /// row (or column), zig-zagging back through the rest and returning
+    /// along the first column (or row). That needs an even number of rows
+    /// (or columns) to close, so boards with both sides odd have none, and
+    /// neither do boards with walls inside.
+    fn new(grid: &Grid, is_wall: impl Fn(i32, i32) -> bool) -> Option<Cycle> {
+        let (columns, rows) = (grid.width - 2, grid.height - 2);
+        if columns < 2 || rows < 2 {
+            return None;
+        }
+        let cells: Vec<(i32, i32)> = if rows % 2 == 0 {
+            zigzag(columns, rows)
+        } else if columns % 2 == 0 {
+            zigzag(rows, columns).into_iter().map(|(y, x)| (x, y)).collect()
+        } else {
+            return None;
+        };
+        if cells.iter().any(|(x, y)| is_wall(*x, *y)) {
+            return None;
+        }
+
+        let mut order = vec![None; (grid.width * grid.height) as usize];
+        for (index, (x, y)) in cells.iter().enumerate() {
+            order[(y * grid.width + x) as usize] = Some(index);
+        }
+        Some(Cycle {
+            width: grid.width,
+            height: grid.height,
+            cells,
+            order,
+        })
+    }
+
+    fn fits(&self, grid: &Grid) -> bool {
+        self.width == grid.width && self.height == grid.height
+    }
+
+    fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn index(&self, (x, y): (i32, i32)) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        self.order[(y * self.width + x) as usize]
+    }
+
+    /// The cell `step` places after `index`, wrapping round the cycle.
+    fn cell_at(&self, index: usize, step: isize) -> (i32, i32) {
+        let len = self.len() as isize;
+        self.cells[(index as isize + step).rem_euclid(len) as usize]
+    }
+
+    /// How many steps it takes to walk from `from` to `to` in the direction
+    /// of `step`.
+    fn distance(&self, from: (i32, i32), to: (i32, i32), step: isize) -> Option<usize> {
+        let len = self.len() as isize;
+        let delta = (self.index(to)? as isize - self.index(from)? as isize) * step;
+        Some(delta.rem_euclid(len) as usize)
+    }
+}
+
+/// A cycle over `columns` x `rows` cells starting at (1, 1) for an even
+/// number of rows: right along row 1, snaking back and forth over the other
+/// rows without touching column 1, then up column 1 to the start.
+fn zigzag(columns: i32, rows: i32) -> Vec<(i32, i32)> {
+    let mut cells: Vec<(i32, i32)> = (1..=columns).map(|x| (x, 1)).collect();
+    for y in 2..=rows {
+        if y % 2 == 0 {
+            cells.extend((2..=columns).rev().map(|x| (x, y)));
+        } else {
+            cells.extend((2..=columns).map(|x| (x, y)));
+        }
+    }
+    cells.extend((2..=rows).rev().map(|y| (1, y)));
+    cells
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::{GameState, World};
+
+    fn is_adjacent((ax, ay): (i32, i32), (bx, by): (i32, i32)) -> bool {
+        (ax - bx).abs() + (ay - by).abs() == 1
+    }
+
+    #[test]
+    fn cycle_visits_every_cell_once() {
+        for (width, height) in [(6, 6), (7, 6), (6, 9), (12, 12)] {
+            let grid = Grid::new(width, height);
+            let cycle = Cycle::new(&grid, |_, _| false).unwrap();
+            assert_eq!(cycle.len(), ((width - 2) * (height - 2)) as usize);
+            for x in 1..width - 1 {
+                for y in 1..height - 1 {
+                    assert!(cycle.index((x, y)).is_some(), "({x}, {y}) missing");
+                }
+            }
+            for index in 0..cycle.len() {
+                assert!(is_adjacent(cycle.cells[index], cycle.cell_at(index, 1)));
+            }
+        }
+    }
+
+    #[test]
+    fn no_cycle_when_both_sides_are_odd() {
+        assert!(Cycle::new(&Grid::new(15, 15), |_, _| false).is_none());
+        assert!(Cycle::new(&Grid::new(6, 6), |x, y| (x, y) == (2, 3)).is_none());
+    }
+
+    #[test]
+    fn has_cycle_needs_an_even_side() {
+        assert!(!has_cycle(&Level::empty(15, 15)));
+        assert!(has_cycle(&Level::empty(16, 15)));
+    }
+
+    #[test]
+    fn distance_follows_the_orientation() {
+        let cycle = Cycle::new(&Grid::new(6, 6), |_, _| false).unwrap();
+        assert_eq!(cycle.distance((1, 1), (2, 1), 1), Some(1));
+        assert_eq!(cycle.distance((1, 1), (2, 1), -1), Some(15));
+        assert_eq!(cycle.distance((1, 1), (0, 0), 1), None);
+    }
+
+    #[test]
+    fn fills_the_board() {
+        let mut world = World::new(6, 6, 3);
+        let mut bot = HamiltonBot::new();
+        world.start();
+        for _ in 0..2000 {
+            if *world.state() != GameState::Playing {
+                break;
+            }
+            let turn = bot.next_turn(&world.view());
+            world.step_with(turn);
+        }
+        assert_eq!(world.snake().len(), 16);
+        assert!(matches!(world.state(), GameState::Victory { .. }));
+    }
+
+    #[test]
+    fn takes_shortcuts_while_short() {
+        // The cycle runs right to the end of the row and back along the top,
+        // so food just above and behind the head is a cut upwards away.
+        let mut world = World::new(12, 12, 0);
+        world.set_food_position(3, 1);
+        world.start();
+        assert_eq!(HamiltonBot::new().next_turn(&world.view()), Some(Direction::Up));
+    }
+}
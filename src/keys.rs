@@ -0,0 +1,251 @@
+use piston_window as pw;
+use snake::snake::Direction;
+
+/// Something a key can do. The game reacts to actions rather than keys, so
+/// any key can be bound to any action through the [`KeyMap`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    TurnUp,
+    TurnDown,
+    TurnLeft,
+    TurnRight,
+    /// Player two's turns in a local match.
+    P2TurnUp,
+    P2TurnDown,
+    P2TurnLeft,
+    P2TurnRight,
+    Start,
+    Pause,
+    Restart,
+    Quit,
+    Save,
+    Load,
+    ToggleAutopilot,
+    CycleMode,
+    CycleDifficulty,
+    CyclePlayers,
+    Rebind,
+    /// Replay speed and single steps while watching a replay.
+    ReplaySpeed,
+    ReplayStep,
+}
+
+impl Action {
+    pub const ALL: [Action; 21] = [
+        Action::TurnUp,
+        Action::TurnDown,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::P2TurnUp,
+        Action::P2TurnDown,
+        Action::P2TurnLeft,
+        Action::P2TurnRight,
+        Action::Start,
+        Action::Pause,
+        Action::Restart,
+        Action::Quit,
+        Action::Save,
+        Action::Load,
+        Action::ToggleAutopilot,
+        Action::CycleMode,
+        Action::CycleDifficulty,
+        Action::CyclePlayers,
+        Action::Rebind,
+        Action::ReplaySpeed,
+        Action::ReplayStep,
+    ];
+
+    /// The name used in the config file and on the rebind screen.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::TurnUp => "up",
+            Action::TurnDown => "down",
+            Action::TurnLeft => "left",
+            Action::TurnRight => "right",
+            Action::P2TurnUp => "p2_up",
+            Action::P2TurnDown => "p2_down",
+            Action::P2TurnLeft => "p2_left",
+            Action::P2TurnRight => "p2_right",
+            Action::Start => "start",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Quit => "quit",
+            Action::Save => "save",
+            Action::Load => "load",
+            Action::ToggleAutopilot => "autopilot",
+            Action::CycleMode => "mode",
+            Action::CycleDifficulty => "difficulty",
+            Action::CyclePlayers => "players",
+            Action::Rebind => "rebind",
+            Action::ReplaySpeed => "replay_speed",
+            Action::ReplayStep => "replay_step",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// The player and direction of a turn action.
+    pub fn turn(&self) -> Option<(usize, Direction)> {
+        match self {
+            Action::TurnUp => Some((0, Direction::Up)),
+            Action::TurnDown => Some((0, Direction::Down)),
+            Action::TurnLeft => Some((0, Direction::Left)),
+            Action::TurnRight => Some((0, Direction::Right)),
+            Action::P2TurnUp => Some((1, Direction::Up)),
+            Action::P2TurnDown => Some((1, Direction::Down)),
+            Action::P2TurnLeft => Some((1, Direction::Left)),
+            Action::P2TurnRight => Some((1, Direction::Right)),
+            _ => None,
+        }
+    }
+
+    fn default_keys(&self) -> Vec<pw::Key> {
+        match self {
+            Action::TurnUp => vec![pw::Key::Up, pw::Key::W],
+            Action::TurnDown => vec![pw::Key::Down, pw::Key::S],
+            Action::TurnLeft => vec![pw::Key::Left, pw::Key::A],
+            Action::TurnRight => vec![pw::Key::Right, pw::Key::D],
+            Action::P2TurnUp => vec![pw::Key::Up],
+            Action::P2TurnDown => vec![pw::Key::Down],
+            Action::P2TurnLeft => vec![pw::Key::Left],
+            Action::P2TurnRight => vec![pw::Key::Right],
+            Action::Start => vec![pw::Key::Return, pw::Key::Space],
+            Action::Pause => vec![pw::Key::Space],
+            Action::Restart => vec![pw::Key::R],
+            Action::Quit => vec![pw::Key::Escape],
+            Action::Save => vec![pw::Key::F5],
+            Action::Load => vec![pw::Key::L],
+            Action::ToggleAutopilot => vec![pw::Key::T],
+            Action::CycleMode => vec![pw::Key::M],
+            Action::CycleDifficulty => vec![pw::Key::D],
+            Action::CyclePlayers => vec![pw::Key::P],
+            Action::Rebind => vec![pw::Key::K],
+            Action::ReplaySpeed => vec![pw::Key::F],
+            Action::ReplayStep => vec![pw::Key::N],
+        }
+    }
+}
+
+/// Which keys trigger which actions. A key may trigger several actions and
+/// the game picks the one that fits the moment: `D` turns right while
+/// playing and changes the difficulty in the menu.
+///
+/// The default keeps arrows and WASD for turning; in a local match the keys
+/// bound to player two's turns (the arrows) steer player two only.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyMap {
+    /// Keys by action, in the order of [`Action::ALL`].
+    keys: Vec<Vec<pw::Key>>,
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap {
+            keys: Action::ALL.iter().map(Action::default_keys).collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    pub fn keys(&self, action: Action) -> &[pw::Key] {
+        &self.keys[index(action)]
+    }
+
+    pub fn set(&mut self, action: Action, keys: Vec<pw::Key>) {
+        self.keys[index(action)] = keys;
+    }
+
+    /// Puts `action` back on its default keys.
+    pub fn reset(&mut self, action: Action) {
+        self.set(action, action.default_keys());
+    }
+
+    /// Every action `key` triggers, in the order of [`Action::ALL`].
+    pub fn actions(&self, key: pw::Key) -> Vec<Action> {
+        Action::ALL
+            .into_iter()
+            .filter(|action| self.keys(*action).contains(&key))
+            .collect()
+    }
+}
+
+fn index(action: Action) -> usize {
+    Action::ALL.iter().position(|other| *other == action).expect("every action is listed")
+}
+
+/// The name of `key` as `key_from_name` reads it, except that Return shows
+/// as Enter.
+pub fn key_name(key: pw::Key) -> String {
+    match key {
+        pw::Key::Return => "Enter".to_string(),
+        _ => format!("{:?}", key),
+    }
+}
+
+/// Looks a key up by its piston name, ignoring case: `W`, `Up`, `Return`,
+/// `Space`, `F5`, `D1` for the 1 key, `NumPad8` and so on. `Enter` is
+/// accepted for `Return`.
+pub fn key_from_name(name: &str) -> Option<pw::Key> {
+    if name.eq_ignore_ascii_case("enter") {
+        return Some(pw::Key::Return);
+    }
+    // Piston numbers keys like SDL: printable ones by character, the rest
+    // from 0x40000039 up.
+    (0..0x80u32)
+        .chain(0x4000_0039..=0x4000_011A)
+        .map(pw::Key::from)
+        .filter(|key| *key != pw::Key::Unknown)
+        .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_keys_by_name() {
+        assert_eq!(key_from_name("w"), Some(pw::Key::W));
+        assert_eq!(key_from_name("Up"), Some(pw::Key::Up));
+        assert_eq!(key_from_name("numpad8"), Some(pw::Key::NumPad8));
+        assert_eq!(key_from_name("F5"), Some(pw::Key::F5));
+        assert_eq!(key_from_name("Semicolon"), Some(pw::Key::Semicolon));
+        assert_eq!(key_from_name("Enter"), Some(pw::Key::Return));
+        assert_eq!(key_from_name("Unknown"), None);
+        assert_eq!(key_from_name("Jump"), None);
+    }
+
+    #[test]
+    fn key_names_read_back() {
+        for key in [pw::Key::Return, pw::Key::NumPad8, pw::Key::D1, pw::Key::Q] {
+            assert_eq!(key_from_name(&key_name(key)), Some(key));
+        }
+    }
+
+    #[test]
+    fn action_names_round_trip() {
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(action));
+        }
+    }
+
+    #[test]
+    fn default_map_keeps_the_classic_keys() {
+        let map = KeyMap::default();
+        assert_eq!(map.actions(pw::Key::W), vec![Action::TurnUp]);
+        assert_eq!(map.actions(pw::Key::Up), vec![Action::TurnUp, Action::P2TurnUp]);
+        assert_eq!(map.actions(pw::Key::Space), vec![Action::Start, Action::Pause]);
+        assert_eq!(map.actions(pw::Key::D), vec![Action::TurnRight, Action::CycleDifficulty]);
+    }
+
+    #[test]
+    fn rebinding_replaces_the_keys() {
+        let mut map = KeyMap::default();
+        map.set(Action::TurnUp, vec![pw::Key::Z]);
+        assert_eq!(map.actions(pw::Key::Z), vec![Action::TurnUp]);
+        assert_eq!(map.actions(pw::Key::W), Vec::new());
+        map.reset(Action::TurnUp);
+        assert_eq!(map.keys(Action::TurnUp), &[pw::Key::Up, pw::Key::W]);
+    }
+}
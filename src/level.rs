@@ -0,0 +1,371 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::snake::{Direction, Snake};
+
+const START_LENGTH: i32 = 3;
+
+/// The smallest board an empty level fits on: the snake spawns with its
+/// head at (4, 2), one cell inside the border.
+pub const MIN_WIDTH: i32 = 6;
+pub const MIN_HEIGHT: i32 = 4;
+/// Boards larger than this on either side no longer fit a window or terminal.
+pub const MAX_SIDE: i32 = 100;
+
+/// A play field: its size, interior walls, where the snake starts and
+/// where the first food lies.
+///
+/// Levels are plain text files. Optional `name` and `direction` lines come
+/// first, then a `map` line followed by one row of characters per board row:
+///
+/// ```text
+/// name Pillars
+/// direction right
+/// map
+/// ##########
+/// #..@.....#
+/// #...#..*.#
+/// ##########
+/// ```
+///
+/// `#` is a wall (the outer ring is always the border), `.` is floor, `@` is
+/// the snake head and `*` the initial food. The rest of the snake trails
+/// behind the head, opposite to `direction`. Lines starting with `#` before
+/// the map are comments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub walls: Vec<(i32, i32)>,
+    pub spawn: (i32, i32),
+    pub direction: Direction,
+    pub food: Option<(i32, i32)>,
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(err) => write!(f, "io error: {}", err),
+            LevelError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<std::io::Error> for LevelError {
+    fn from(err: std::io::Error) -> Self {
+        LevelError::Io(err)
+    }
+}
+
+impl Level {
+    /// The classic empty rectangle.
+    pub fn empty(width: i32, height: i32) -> Level {
+        Level {
+            name: "Empty".to_string(),
+            width,
+            height,
+            walls: Vec::new(),
+            spawn: (4, 2),
+            direction: Direction::Right,
+            // Boards too small for the usual spot start without food.
+            food: Some((6, 4)).filter(|(x, y)| *x < width - 1 && *y < height - 1),
+        }
+    }
+
+    /// The cells of a freshly spawned snake, head first.
+    pub fn spawn_body(&self) -> Vec<(i32, i32)> {
+        trailing_body(self.spawn, self.direction)
+    }
+
+    pub fn spawn_snake(&self) -> Snake {
+        Snake::from_parts(&self.spawn_body(), self.direction)
+    }
+
+    /// A second snake mirrored through the centre of the board, facing the
+    /// other way. Used for head-to-head play.
+    pub fn mirrored_spawn_snake(&self) -> Snake {
+        let head = (self.width - 1 - self.spawn.0, self.height - 1 - self.spawn.1);
+        let direction = self.direction.opposite();
+        Snake::from_parts(&trailing_body(head, direction), direction)
+    }
+
+    /// Starting snakes for `count` players: the spawn, its mirror image, then
+    /// one snake per evenly spaced row, alternating between facing right from
+    /// the left side and left from the right side. They are not checked
+    /// against walls or each other.
+    pub fn spawn_snakes(&self, count: usize) -> Vec<Snake> {
+        (0..count)
+            .map(|index| match index {
+                0 => self.spawn_snake(),
+                1 => self.mirrored_spawn_snake(),
+                _ => {
+                    let y = index as i32 * (self.height - 1) / count as i32;
+                    let (head, direction) = if index % 2 == 0 {
+                        ((START_LENGTH + 1, y), Direction::Right)
+                    } else {
+                        ((self.width - 2 - START_LENGTH, y), Direction::Left)
+                    };
+                    Snake::from_parts(&trailing_body(head, direction), direction)
+                }
+            })
+            .collect()
+    }
+
+    pub fn encode(&self) -> String {
+        let mut rows = vec![vec!['.'; self.width as usize]; self.height as usize];
+        for (y, row) in rows.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let (x, y) = (x as i32, y as i32);
+                if x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1 {
+                    *cell = '#';
+                }
+            }
+        }
+        let mut put = |(x, y): (i32, i32), c: char| rows[y as usize][x as usize] = c;
+        for wall in &self.walls {
+            put(*wall, '#');
+        }
+        if let Some(food) = self.food {
+            put(food, '*');
+        }
+        put(self.spawn, '@');
+
+        let mut out = format!("name {}\ndirection {}\nmap\n", self.name, direction_name(self.direction));
+        for row in rows {
+            out.extend(row);
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn parse(text: &str) -> Result<Level, LevelError> {
+        let mut name = "Untitled".to_string();
+        let mut direction = Direction::Right;
+        let mut lines = text.lines().enumerate();
+
+        let map_start = loop {
+            let Some((index, line)) = lines.next() else {
+                return Err(parse_error(text.lines().count(), "missing 'map' section"));
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once(' ').unwrap_or((line, "")) {
+                ("name", value) => name = value.trim().to_string(),
+                ("direction", value) => {
+                    direction = parse_direction(value.trim())
+                        .ok_or_else(|| parse_error(index + 1, format!("unknown direction {:?}", value.trim())))?;
+                }
+                ("map", _) => break index + 1,
+                (key, _) => return Err(parse_error(index + 1, format!("unknown key {:?}", key))),
+            }
+        };
+
+        let rows: Vec<(usize, &str)> = lines
+            .map(|(index, line)| (index + 1, line.trim_end()))
+            .filter(|(_, line)| !line.is_empty())
+            .collect();
+        if rows.len() < 3 {
+            return Err(parse_error(map_start, "map needs at least 3 rows"));
+        }
+
+        let width = rows[0].1.chars().count() as i32;
+        let height = rows.len() as i32;
+        let mut walls = Vec::new();
+        let mut spawn = None;
+        let mut food = None;
+
+        for (y, (line_no, row)) in rows.iter().enumerate() {
+            if row.chars().count() as i32 != width {
+                return Err(parse_error(*line_no, format!("row is not {} cells wide", width)));
+            }
+            for (x, c) in row.chars().enumerate() {
+                let cell = (x as i32, y as i32);
+                let on_border = cell.0 == 0 || cell.1 == 0 || cell.0 == width - 1 || cell.1 == height - 1;
+                match (c, on_border) {
+                    ('#', true) => {}
+                    (_, true) => return Err(parse_error(*line_no, "the outer ring must be '#'")),
+                    ('#', false) => walls.push(cell),
+                    ('.' | ' ', false) => {}
+                    ('@', false) if spawn.is_none() => spawn = Some(cell),
+                    ('*', false) if food.is_none() => food = Some(cell),
+                    ('@' | '*', false) => {
+                        return Err(parse_error(*line_no, format!("more than one '{}'", c)))
+                    }
+                    (other, false) => return Err(parse_error(*line_no, format!("unknown cell {:?}", other))),
+                }
+            }
+        }
+
+        let spawn = spawn.ok_or_else(|| parse_error(map_start, "map has no snake head '@'"))?;
+        let level = Level {
+            name,
+            width,
+            height,
+            walls,
+            spawn,
+            direction,
+            food,
+        };
+
+        for cell in level.spawn_body() {
+            let inside = cell.0 > 0 && cell.1 > 0 && cell.0 < width - 1 && cell.1 < height - 1;
+            if !inside || level.walls.contains(&cell) {
+                return Err(parse_error(map_start, "no room behind '@' for the snake's body"));
+            }
+        }
+        Ok(level)
+    }
+}
+
+fn trailing_body((x, y): (i32, i32), direction: Direction) -> Vec<(i32, i32)> {
+    let [dx, dy] = direction.as_array();
+    (0..START_LENGTH).map(|i| (x - dx * i, y - dy * i)).collect()
+}
+
+pub fn load_from(path: &Path) -> Result<Level, LevelError> {
+    let content = fs::read_to_string(path)?;
+    Level::parse(&content)
+}
+
+fn direction_name(dir: Direction) -> &'static str {
+    match dir {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+fn parse_direction(value: &str) -> Option<Direction> {
+    match value {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> LevelError {
+    LevelError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str = "\
+name Sample
+direction down
+map
+#######
+#.....#
+#.....#
+#..@..#
+#.#.*.#
+#######
+";
+
+    #[test]
+    fn parse_reads_header_and_map() {
+        let level = Level::parse(SAMPLE).unwrap();
+        assert_eq!(level.name, "Sample");
+        assert_eq!((level.width, level.height), (7, 6));
+        assert_eq!(level.walls, vec![(2, 4)]);
+        assert_eq!(level.spawn, (3, 3));
+        assert_eq!(level.direction, Direction::Down);
+        assert_eq!(level.food, Some((4, 4)));
+    }
+
+    #[test]
+    fn encode_parse_roundtrip() {
+        let level = Level::parse(SAMPLE).unwrap();
+        assert_eq!(Level::parse(&level.encode()).unwrap(), level);
+    }
+
+    #[test]
+    fn small_empty_level_keeps_food_on_the_board() {
+        assert_eq!(Level::empty(6, 6).food, None);
+        assert_eq!(Level::empty(8, 6).food, Some((6, 4)));
+    }
+
+    #[test]
+    fn empty_level_roundtrips() {
+        let level = Level::empty(15, 15);
+        assert_eq!(Level::parse(&level.encode()).unwrap(), level);
+    }
+
+    #[test]
+    fn spawn_body_trails_behind_head() {
+        let level = Level::empty(15, 15);
+        assert_eq!(level.spawn_body(), vec![(4, 2), (3, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn mirrored_spawn_faces_the_other_way() {
+        let snake = Level::empty(15, 15).mirrored_spawn_snake();
+        assert_eq!(snake.body_positions(), vec![(10, 12), (11, 12), (12, 12)]);
+        assert_eq!(snake.head_direction(), Direction::Left);
+    }
+
+    #[test]
+    fn extra_snakes_spawn_on_spaced_rows() {
+        let snakes = Level::empty(15, 15).spawn_snakes(4);
+        let heads: Vec<(i32, i32)> = snakes.iter().map(Snake::head_position).collect();
+        assert_eq!(heads, vec![(4, 2), (10, 12), (4, 7), (10, 10)]);
+        assert_eq!(snakes[3].head_direction(), Direction::Left);
+    }
+
+    #[test]
+    fn parse_rejects_ragged_rows() {
+        let text = "map\n#####\n#.@..#\n#####\n";
+        assert!(matches!(Level::parse(text), Err(LevelError::Parse { line: 3, .. })));
+    }
+
+    #[test]
+    fn parse_rejects_open_border() {
+        let text = "map\n#####\n#..@.\n#####\n";
+        assert!(matches!(Level::parse(text), Err(LevelError::Parse { line: 3, .. })));
+    }
+
+    #[test]
+    fn parse_rejects_missing_head() {
+        let text = "map\n#####\n#...#\n#####\n";
+        assert!(Level::parse(text).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_body_in_wall() {
+        let text = "direction right\nmap\n######\n#.#@.#\n######\n";
+        assert!(Level::parse(text).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_unknown_key() {
+        let text = "speed 3\nmap\n#####\n#..@#\n#####\n";
+        assert!(matches!(Level::parse(text), Err(LevelError::Parse { line: 1, .. })));
+    }
+
+    #[test]
+    fn bundled_levels_parse() {
+        for text in [include_str!("../levels/pillars.txt"), include_str!("../levels/corridor.txt")] {
+            let level = Level::parse(text).unwrap();
+            assert!(!level.walls.is_empty());
+        }
+    }
+}
@@ -0,0 +1,18 @@
+pub mod arena;
+pub mod autopilot;
+pub mod bots;
+pub mod controller;
+pub mod difficulty;
+pub mod food;
+pub mod gym;
+pub mod hamilton;
+pub mod level;
+pub mod persistence;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod score;
+pub mod sim;
+pub mod snake;
+pub mod tick;
+pub mod world;
@@ -1,29 +1,128 @@
 mod audio;
+mod cli;
+mod config;
 mod debug;
 mod draw;
 mod game;
-mod persistence;
-mod snake;
+mod gamepad;
+mod keys;
+mod menu;
 #[cfg(feature = "spectator")]
 mod spectator;
+#[cfg(feature = "tui")]
+mod tui;
 
 use crate::audio::SoundPlayer;
+use crate::cli::Options;
+use crate::config::Config;
 use crate::draw::to_coord_u32;
 use crate::game::Game;
+use crate::gamepad::Gamepad;
+use snake::bots;
+use snake::hamilton;
+use snake::level;
+use snake::persistence;
+use snake::replay;
+use snake::rng::GameRng;
 #[cfg(feature = "spectator")]
 use crate::spectator as spectator_server;
 
 use piston_window as pw;
-use piston_window::{PressEvent, UpdateEvent};
+use piston_window::{AdvancedWindow, PressEvent, UpdateEvent, Window};
 use piston_window::graphics::Transformed;
 
-const BACK_COLOR: pw::graphics::types::Color = [0.5, 0.5, 0.5, 1.0];
-const WIDTH: i32 = 15;
-const HEIGHT: i32 = WIDTH;
+const DEFAULT_WIDTH: i32 = 15;
+const DEFAULT_HEIGHT: i32 = 15;
+#[cfg(feature = "spectator")]
+const DEFAULT_SPECTATOR_WS: &str = "0.0.0.0:9001";
+#[cfg(feature = "spectator")]
+const DEFAULT_SPECTATOR_HTTP: &str = "0.0.0.0:8000";
 const GAME_TITLE: &str = "Snake";
 
 fn main() {
-    let mut piston_window: pw::PistonWindow = pw_from_constants();
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => fail(&e.to_string()),
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    // Defaults < config file < command line.
+    let config = load_config(options.config.as_deref());
+    let options = options.or(config.options);
+
+    let seed = options.seed.unwrap_or_else(GameRng::random_seed);
+    println!("Seed: {}", seed);
+
+    let sound_player = if options.mute.unwrap_or(false) { None } else { SoundPlayer::new(config.volume) };
+    let width = options.width.unwrap_or(DEFAULT_WIDTH);
+    let height = options.height.unwrap_or(DEFAULT_HEIGHT);
+    let mut snake_game: Game = Game::new(width, height, seed, sound_player);
+    let high_score_path = options
+        .high_score_path
+        .clone()
+        .unwrap_or_else(|| persistence::HIGHSCORE_FILE.into());
+    snake_game.set_high_score_path(Some(high_score_path));
+    snake_game.set_interpolation(options.interpolate.unwrap_or(false));
+    snake_game.set_palette(config.palette);
+    snake_game.set_key_map(config.keys);
+    // Menu changes and rebound keys go to the file the settings came from.
+    snake_game.set_config_path(options.config.clone().or_else(config::default_path));
+    if let Some(path) = &options.level {
+        match level::load_from(path) {
+            Ok(level) => snake_game.load_level(level),
+            Err(e) => fail(&format!("failed to load level {}: {}", path.display(), e)),
+        }
+    }
+    if let Some(mode) = options.mode {
+        snake_game.set_mode(mode);
+    }
+    if let Some(difficulty) = options.difficulty {
+        snake_game.set_difficulty(difficulty);
+    }
+    snake_game.set_speed_curve(options.speed_curve);
+    let bots = options.bots.as_deref().unwrap_or_default();
+    for (player, name) in bots.iter().enumerate() {
+        if let Some(bot) = bots::by_name(name, seed.wrapping_add(player as u64)) {
+            snake_game.set_controller(player, bot);
+        }
+    }
+    if bots.iter().any(|name| name == "hamilton") && !hamilton::has_cycle(snake_game.level()) {
+        let level = snake_game.level();
+        eprintln!(
+            "hamilton has no cycle on a {}x{} board and plays like autopilot; \
+             it needs an even --width or --height and no walls, e.g. --width 16",
+            level.width, level.height
+        );
+    }
+    // The player count saved from the menu never leaves bots without a seat.
+    let players = options.players.unwrap_or(1).max(bots.len());
+    if !snake_game.set_players(players) {
+        fail(&format!("this board has no room for {} snakes", players));
+    }
+    if options.resume {
+        snake_game.load_game();
+    }
+    if let Some(path) = &options.record {
+        snake_game.record_to(path.clone());
+    }
+    if let Some(path) = &options.replay {
+        match replay::load_from(path) {
+            Ok(recorded) => snake_game.play_replay(recorded),
+            Err(e) => fail(&format!("failed to load replay {}: {}", path.display(), e)),
+        }
+    }
+
+    if options.tui {
+        run_in_terminal(&mut snake_game);
+        return;
+    }
+
+    // Levels, saves and replays can change the board size, so the window is
+    // built once the game knows which board it is on.
+    let (width, height) = snake_game.board_size();
+    let mut piston_window: pw::PistonWindow = pw_for_board(width, height);
 
     // Load font for score display - try common Windows font paths
     let font_path = find_font();
@@ -31,23 +130,39 @@ fn main() {
         .load_font(font_path, pw::wgpu_graphics::TextureSettings::new())
         .expect("Failed to load font");
 
-    let sound_player = SoundPlayer::new();
-    let mut snake_game: Game = Game::new(WIDTH, HEIGHT, sound_player);
-    #[cfg(feature = "spectator")]
-    let spectator = spectator_server::start("0.0.0.0:9001");
     #[cfg(feature = "spectator")]
-    spectator_server::start_http("0.0.0.0:8000");
-
-    let base_width = to_coord_u32(WIDTH) as f64;
-    let base_height = to_coord_u32(HEIGHT) as f64;
+    let spectator = {
+        let ws_address = options.spectator_ws.as_deref().unwrap_or(DEFAULT_SPECTATOR_WS);
+        let http_address = options.spectator_http.as_deref().unwrap_or(DEFAULT_SPECTATOR_HTTP);
+        let ws_port = cli::port(ws_address).expect("addresses are checked when parsed");
+        spectator_server::start_http(http_address, ws_port);
+        spectator_server::start(ws_address)
+    };
 
+    let mut gamepad = Gamepad::new(config.deadzone);
+    let mut window_board = (width, height);
     while let Some(event) = piston_window.next() {
+        let (width, height) = snake_game.board_size();
+        // The board size can change in the menu.
+        if (width, height) != window_board {
+            piston_window.set_size([to_coord_u32(width), to_coord_u32(height)]);
+            window_board = (width, height);
+        }
+        let base_width = to_coord_u32(width) as f64;
+        let base_height = to_coord_u32(height) as f64;
+
         if let Some(pw::Button::Keyboard(key)) = event.press_args() {
             snake_game.key_pressed(key);
         }
+        while let Some(actions) = gamepad.next_actions() {
+            snake_game.perform(&actions);
+        }
+        if snake_game.wants_to_quit() {
+            piston_window.set_should_close(true);
+        }
 
         piston_window.draw_2d(&event, |c, g, _device| {
-            pw::graphics::clear(BACK_COLOR, g);
+            pw::graphics::clear(snake_game.palette().background, g);
             let (win_w, win_h) = c.viewport
                 .map(|vp| (vp.window_size[0], vp.window_size[1]))
                 .unwrap_or((base_width, base_height));
@@ -66,6 +181,42 @@ fn main() {
             spectator.send(snake_game.game_snapshot());
         });
     }
+
+    // Closing the window mid-run keeps the game (and its replay) for the next start.
+    snake_game.shutdown();
+}
+
+/// Plays without a window, drawing the board in the terminal.
+#[cfg(feature = "tui")]
+fn run_in_terminal(game: &mut Game) {
+    if let Err(e) = tui::run(game) {
+        eprintln!("Terminal front end failed: {}", e);
+    }
+    game.shutdown();
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_in_terminal(_game: &mut Game) {
+    eprintln!("This build has no terminal front end, rebuild with `--features tui` to use --tui");
+    std::process::exit(2);
+}
+
+/// Reads the config file at `path`, or the default one if it exists.
+fn load_config(path: Option<&std::path::Path>) -> Config {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => match config::default_path().filter(|path| path.exists()) {
+            Some(path) => path,
+            None => return Config::default(),
+        },
+    };
+    config::load_from(&path).unwrap_or_else(|e| fail(&format!("{}: {}", path.display(), e)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("snake: {}", message);
+    eprintln!("Run with --help for the list of options.");
+    std::process::exit(2);
 }
 
 fn find_font() -> std::path::PathBuf {
@@ -96,10 +247,11 @@ fn find_font() -> std::path::PathBuf {
     std::path::PathBuf::from("/System/Library/Fonts/Helvetica.ttc")
 }
 
-fn pw_from_constants() -> pw::PistonWindow {
-    pw::WindowSettings::new(GAME_TITLE, [to_coord_u32(WIDTH), to_coord_u32(HEIGHT)])
+fn pw_for_board(width: i32, height: i32) -> pw::PistonWindow {
+    pw::WindowSettings::new(GAME_TITLE, [to_coord_u32(width), to_coord_u32(height)])
         .resizable(true)
-        .exit_on_esc(true)
+        // Escape is an action like any other, see `Game::wants_to_quit`.
+        .exit_on_esc(false)
         .build()
         .expect("Failed to create window")
 }
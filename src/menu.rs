@@ -0,0 +1,170 @@
+/// The pages of the main menu.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Page {
+    #[default]
+    Main,
+    Options,
+    HighScores,
+}
+
+impl Page {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Page::Main => "SNAKE",
+            Page::Options => "Options",
+            Page::HighScores => "High scores",
+        }
+    }
+}
+
+/// A line of the menu the player can select.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Play,
+    /// Only listed while there is a saved game.
+    Resume,
+    Mode,
+    Difficulty,
+    BoardSize,
+    Options,
+    HighScores,
+    Quit,
+    Players,
+    SmoothMovement,
+    Volume,
+    Keys,
+    Back,
+}
+
+impl Item {
+    /// The text in front of the item's value, or the whole line for items
+    /// without one.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Item::Play => "Play",
+            Item::Resume => "Resume saved game",
+            Item::Mode => "Mode",
+            Item::Difficulty => "Difficulty",
+            Item::BoardSize => "Board",
+            Item::Options => "Options",
+            Item::HighScores => "High scores",
+            Item::Quit => "Quit",
+            Item::Players => "Players",
+            Item::SmoothMovement => "Smooth movement",
+            Item::Volume => "Volume",
+            Item::Keys => "Keys",
+            Item::Back => "Back",
+        }
+    }
+
+    /// Whether Left and Right step through the item's values.
+    pub fn has_value(&self) -> bool {
+        matches!(
+            self,
+            Item::Mode | Item::Difficulty | Item::BoardSize | Item::Players | Item::SmoothMovement | Item::Volume
+        )
+    }
+}
+
+/// The open page and the item selected on it. The selection is kept by item
+/// rather than by index, so it stays put when the resume entry comes and goes.
+#[derive(Clone, Debug, Default)]
+pub struct Menu {
+    page: Page,
+    selected: Option<Item>,
+}
+
+impl Menu {
+    pub fn page(&self) -> Page {
+        self.page
+    }
+
+    pub fn items(&self, save_available: bool) -> Vec<Item> {
+        match self.page {
+            Page::Main => {
+                let mut items = vec![Item::Play];
+                if save_available {
+                    items.push(Item::Resume);
+                }
+                items.extend([
+                    Item::Mode,
+                    Item::Difficulty,
+                    Item::BoardSize,
+                    Item::Options,
+                    Item::HighScores,
+                    Item::Quit,
+                ]);
+                items
+            }
+            Page::Options => vec![
+                Item::Players,
+                Item::SmoothMovement,
+                Item::Volume,
+                Item::Keys,
+                Item::Back,
+            ],
+            Page::HighScores => vec![Item::Back],
+        }
+    }
+
+    /// The selected item among `items`, the first one if none is.
+    pub fn selected(&self, items: &[Item]) -> Item {
+        self.selected.filter(|item| items.contains(item)).unwrap_or(items[0])
+    }
+
+    /// Moves the selection `step` items down, wrapping at either end.
+    pub fn step(&mut self, items: &[Item], step: isize) {
+        let current = items.iter().position(|item| *item == self.selected(items)).unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(items.len() as isize) as usize;
+        self.selected = Some(items[next]);
+    }
+
+    pub fn open(&mut self, page: Page) {
+        self.page = page;
+        self.selected = None;
+    }
+
+    /// Returns to the main page, on the entry of the page left.
+    pub fn back(&mut self) {
+        self.selected = match self.page {
+            Page::Main => self.selected,
+            Page::Options => Some(Item::Options),
+            Page::HighScores => Some(Item::HighScores),
+        };
+        self.page = Page::Main;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut menu = Menu::default();
+        let items = menu.items(false);
+        assert_eq!(menu.selected(&items), Item::Play);
+        menu.step(&items, -1);
+        assert_eq!(menu.selected(&items), Item::Quit);
+        menu.step(&items, 1);
+        menu.step(&items, 1);
+        assert_eq!(menu.selected(&items), Item::Mode);
+    }
+
+    #[test]
+    fn selection_survives_the_resume_entry() {
+        let mut menu = Menu::default();
+        menu.step(&menu.items(false), 1);
+        assert_eq!(menu.selected(&menu.items(true)), Item::Mode);
+    }
+
+    #[test]
+    fn back_returns_to_the_page_entry() {
+        let mut menu = Menu::default();
+        menu.open(Page::Options);
+        assert_eq!(menu.selected(&menu.items(false)), Item::Players);
+        menu.back();
+        assert_eq!(menu.page(), Page::Main);
+        assert_eq!(menu.selected(&menu.items(false)), Item::Options);
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt;
 use std::num::ParseIntError;
 use std::path::Path;
 
-const HIGHSCORE_FILE: &str = "highscore.txt";
+pub const HIGHSCORE_FILE: &str = "highscore.txt";
 
 #[derive(Debug)]
 pub enum PersistenceError {
@@ -34,32 +34,65 @@ impl From<ParseIntError> for PersistenceError {
     }
 }
 
-pub fn save_high_score(score: u32) {
-    if let Err(e) = try_save_high_score(score) {
-        eprintln!("Failed to save high score: {}", e);
+/// High scores are kept per bucket (e.g. per board mode and difficulty) so that scores from
+/// easier variants do not bury the others. The file holds one `bucket score`
+/// pair per line.
+pub fn try_save_high_score(path: &Path, bucket: &str, score: u32) -> Result<(), PersistenceError> {
+    let mut scores = try_load_all_high_scores(path)?;
+    match scores.iter_mut().find(|(name, _)| name == bucket) {
+        Some(entry) => entry.1 = score,
+        None => scores.push((bucket.to_string(), score)),
     }
+    fs::write(path, format_high_scores(&scores))?;
+    Ok(())
 }
 
-pub fn load_high_score() -> u32 {
-    try_load_high_score().unwrap_or_else(|e| {
-        eprintln!("Failed to load high score: {}", e);
-        0
-    })
+pub fn try_load_high_score(path: &Path, bucket: &str) -> Result<u32, PersistenceError> {
+    let scores = try_load_all_high_scores(path)?;
+    Ok(scores
+        .into_iter()
+        .find(|(name, _)| name == bucket)
+        .map(|(_, score)| score)
+        .unwrap_or(0))
 }
 
-pub fn try_save_high_score(score: u32) -> Result<(), PersistenceError> {
-    fs::write(HIGHSCORE_FILE, score.to_string())?;
-    Ok(())
+pub fn try_load_all_high_scores(path: &Path) -> Result<Vec<(String, u32)>, PersistenceError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    parse_high_scores(&content)
 }
 
-pub fn try_load_high_score() -> Result<u32, PersistenceError> {
-    if !Path::new(HIGHSCORE_FILE).exists() {
-        return Ok(0);
+pub fn parse_high_scores(content: &str) -> Result<Vec<(String, u32)>, PersistenceError> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Ok(Vec::new());
     }
+    // A lone number is the old format, which counted snake length rather
+    // than points. It is dropped instead of being ranked next to points and
+    // the file is rewritten in the new format on the next high score.
+    if !content.contains(char::is_whitespace) {
+        content.parse::<u32>()?;
+        return Ok(Vec::new());
+    }
+
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (bucket, score) = line.trim().rsplit_once(' ').unwrap_or(("", line));
+            Ok((bucket.trim().to_string(), score.parse::<u32>()?))
+        })
+        .collect()
+}
 
-    let content = fs::read_to_string(HIGHSCORE_FILE)?;
-    let score = content.trim().parse::<u32>()?;
-    Ok(score)
+pub fn format_high_scores(scores: &[(String, u32)]) -> String {
+    scores
+        .iter()
+        .map(|(bucket, score)| format!("{} {}\n", bucket, score))
+        .collect()
 }
 
 #[cfg(test)]
@@ -67,6 +100,10 @@ mod test {
     use std::fs;
     use std::path::Path;
 
+    use super::{
+        format_high_scores, parse_high_scores, try_load_high_score, try_save_high_score,
+    };
+
     // Helper functions that use a custom path for testing
     fn save_high_score_to(path: &str, score: u32) {
         if let Err(e) = fs::write(path, score.to_string()) {
@@ -172,4 +209,48 @@ mod test {
         assert_eq!(loaded, u32::MAX);
         cleanup(path);
     }
+
+    #[test]
+    fn buckets_are_saved_independently() {
+        let path = "test_hs_buckets.txt";
+        cleanup(path);
+
+        try_save_high_score(Path::new(path), "walls", 30).unwrap();
+        try_save_high_score(Path::new(path), "wrap", 12).unwrap();
+        try_save_high_score(Path::new(path), "walls", 45).unwrap();
+
+        assert_eq!(try_load_high_score(Path::new(path), "walls").unwrap(), 45);
+        assert_eq!(try_load_high_score(Path::new(path), "wrap").unwrap(), 12);
+        assert_eq!(try_load_high_score(Path::new(path), "easy").unwrap(), 0);
+        cleanup(path);
+    }
+
+    #[test]
+    fn parse_drops_legacy_length_score() {
+        assert_eq!(parse_high_scores("56\n").unwrap(), Vec::new());
+
+        let path = "test_hs_legacy.txt";
+        fs::write(path, "56").unwrap();
+        assert_eq!(try_load_high_score(Path::new(path), "walls-normal").unwrap(), 0);
+        try_save_high_score(Path::new(path), "walls-normal", 10).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "walls-normal 10\n");
+        cleanup(path);
+    }
+
+    #[test]
+    fn parse_reads_buckets() {
+        let scores = parse_high_scores("walls 56\nwrap 12\n").unwrap();
+        assert_eq!(scores, vec![("walls".to_string(), 56), ("wrap".to_string(), 12)]);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_score() {
+        assert!(parse_high_scores("walls lots\n").is_err());
+    }
+
+    #[test]
+    fn format_parse_roundtrip() {
+        let scores = vec![("walls".to_string(), 3), ("wrap".to_string(), 40)];
+        assert_eq!(parse_high_scores(&format_high_scores(&scores)).unwrap(), scores);
+    }
 }
@@ -0,0 +1,343 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::difficulty::Difficulty;
+use crate::level::Level;
+use crate::rng::GameRng;
+use crate::snake::Direction;
+use crate::world::{BoardMode, StepOutcome, World};
+
+const MAGIC: &str = "snake-replay";
+/// Bumped whenever the rules change how a recorded turn sequence plays out,
+/// since older recordings would no longer reproduce their game.
+const REPLAY_VERSION: u32 = 2;
+
+/// A recorded game: where the random sequence stood when it started and the
+/// turn taken on every tick. Re-running the turns through a [`World`] built
+/// from the same start reproduces the game exactly.
+///
+/// On disk the turns are one character per tick, `.` for going straight and
+/// `U`/`D`/`L`/`R` for a turn, followed by the level the game was played on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub width: i32,
+    pub height: i32,
+    pub seed: u64,
+    pub rng_state: u64,
+    pub mode: BoardMode,
+    pub difficulty: Difficulty,
+    pub level: Level,
+    pub turns: Vec<Option<Direction>>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Format(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "io error: {}", err),
+            ReplayError::Format(msg) => write!(f, "format error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(err: std::io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+impl Replay {
+    /// Starts a recording for a world that is about to play its first tick.
+    pub fn start(world: &World) -> Replay {
+        Replay {
+            width: world.grid().width,
+            height: world.grid().height,
+            seed: world.rng().seed(),
+            rng_state: world.rng().state(),
+            mode: world.mode(),
+            difficulty: world.difficulty(),
+            level: world.level().clone(),
+            turns: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, turn: Option<Direction>) {
+        self.turns.push(turn);
+    }
+
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    /// A fresh world in the state the recording started from.
+    pub fn world(&self) -> World {
+        let mut world = World::with_level(
+            self.level.clone(),
+            GameRng::from_state(self.seed, self.rng_state),
+        );
+        world.set_mode(self.mode);
+        world.set_difficulty(self.difficulty);
+        world.start();
+        world
+    }
+
+    pub fn encode(&self) -> String {
+        let turns: String = self
+            .turns
+            .iter()
+            .map(|turn| match turn {
+                None => '.',
+                Some(Direction::Up) => 'U',
+                Some(Direction::Down) => 'D',
+                Some(Direction::Left) => 'L',
+                Some(Direction::Right) => 'R',
+            })
+            .collect();
+
+        format!(
+            "{} {}\ngrid {} {}\nseed {} {}\nmode {}\ndifficulty {}\nturns {}\nlevel\n{}",
+            MAGIC,
+            REPLAY_VERSION,
+            self.width,
+            self.height,
+            self.seed,
+            self.rng_state,
+            self.mode.name(),
+            self.difficulty.name(),
+            turns,
+            self.level.encode()
+        )
+    }
+
+    pub fn decode(text: &str) -> Result<Replay, ReplayError> {
+        let (text, level) = match text.split_once("\nlevel\n") {
+            Some((head, level)) => (head, Some(level)),
+            None => (text, None),
+        };
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+
+        let header = lines.next().unwrap_or("");
+        if header != format!("{} {}", MAGIC, REPLAY_VERSION) {
+            return Err(ReplayError::Format(format!("unsupported header {:?}", header)));
+        }
+
+        let grid = fields(lines.next(), "grid")?;
+        let seed = fields(lines.next(), "seed")?;
+
+        let mode = fields(lines.next(), "mode")?;
+        let mode = mode
+            .first()
+            .and_then(|name| BoardMode::from_name(name))
+            .ok_or_else(|| ReplayError::Format(format!("unknown mode {:?}", mode.join(" "))))?;
+        let difficulty = fields(lines.next(), "difficulty")?;
+        let difficulty = difficulty
+            .first()
+            .and_then(|name| Difficulty::from_name(name))
+            .ok_or_else(|| ReplayError::Format(format!("unknown difficulty {:?}", difficulty.join(" "))))?;
+
+        let turns = lines
+            .next()
+            .and_then(|line| line.strip_prefix("turns"))
+            .ok_or_else(|| ReplayError::Format("missing 'turns' line".to_string()))?
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '.' => Ok(None),
+                'U' => Ok(Some(Direction::Up)),
+                'D' => Ok(Some(Direction::Down)),
+                'L' => Ok(Some(Direction::Left)),
+                'R' => Ok(Some(Direction::Right)),
+                other => Err(ReplayError::Format(format!("unknown turn {:?}", other))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = number(grid.first(), "grid")?;
+        let height = number(grid.get(1), "grid")?;
+        let level = level.ok_or_else(|| ReplayError::Format("missing 'level' line".to_string()))?;
+        let level = Level::parse(level).map_err(|err| ReplayError::Format(format!("level {}", err)))?;
+
+        Ok(Replay {
+            width,
+            height,
+            seed: number(seed.first(), "seed")?,
+            rng_state: number(seed.get(1), "seed")?,
+            mode,
+            difficulty,
+            level,
+            turns,
+        })
+    }
+}
+
+/// Feeds a [`Replay`] back into a world one tick at a time.
+pub struct ReplayPlayer {
+    replay: Replay,
+    cursor: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        ReplayPlayer { replay, cursor: 0 }
+    }
+
+    /// The world to hand to [`ReplayPlayer::step`], positioned at the start.
+    pub fn world(&self) -> World {
+        self.replay.world()
+    }
+
+    /// Plays the next recorded tick, or returns `None` once the recording is exhausted.
+    pub fn step(&mut self, world: &mut World) -> Option<StepOutcome> {
+        let turn = *self.replay.turns.get(self.cursor)?;
+        self.cursor += 1;
+        Some(world.step_with(turn))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.replay.len()
+    }
+
+    /// Ticks played so far and the total length of the recording.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.cursor, self.replay.len())
+    }
+}
+
+pub fn save_to(path: &Path, replay: &Replay) -> Result<(), ReplayError> {
+    fs::write(path, replay.encode())?;
+    Ok(())
+}
+
+pub fn load_from(path: &Path) -> Result<Replay, ReplayError> {
+    let content = fs::read_to_string(path)?;
+    Replay::decode(&content)
+}
+
+fn fields<'a>(line: Option<&'a str>, key: &str) -> Result<Vec<&'a str>, ReplayError> {
+    let mut parts = line.unwrap_or("").split_whitespace();
+    if parts.next() != Some(key) {
+        return Err(ReplayError::Format(format!("missing '{}' line", key)));
+    }
+    Ok(parts.collect())
+}
+
+fn number<T: std::str::FromStr>(value: Option<&&str>, key: &str) -> Result<T, ReplayError> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| ReplayError::Format(format!("invalid '{}' line", key)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Plays a game with a fixed turn pattern, recording it like the front end does.
+    fn record_game(seed: u64) -> (Replay, World) {
+        let pattern = [Direction::Down, Direction::Right, Direction::Up, Direction::Right];
+        let mut world = World::new(20, 20, seed);
+        world.set_mode(BoardMode::Wrap);
+        world.set_difficulty(Difficulty::Insane);
+        world.start();
+        let mut replay = Replay::start(&world);
+
+        for tick in 0..200 {
+            let turn = (tick % 5 == 0).then(|| pattern[(tick / 5) % pattern.len()]);
+            if world.step_with(turn) == StepOutcome::Idle {
+                break;
+            }
+            replay.record(world.last_turn());
+        }
+        (replay, world)
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let (replay, _) = record_game(5);
+        assert_eq!(Replay::decode(&replay.encode()).unwrap(), replay);
+    }
+
+    #[test]
+    fn playback_honours_level_walls() {
+        let level = Level::parse("map\n#######\n#..@#.#\n#.....#\n#######\n").unwrap();
+        let mut world = World::with_level(level, GameRng::new(1));
+        world.start();
+        let mut replay = Replay::start(&world);
+        while world.step_with(None) != StepOutcome::Died {
+            replay.record(world.last_turn());
+        }
+        replay.record(world.last_turn());
+
+        let decoded = Replay::decode(&replay.encode()).unwrap();
+        let mut player = ReplayPlayer::new(decoded);
+        let mut played = player.world();
+        let mut last = None;
+        while let Some(outcome) = player.step(&mut played) {
+            last = Some(outcome);
+        }
+        assert_eq!(last, Some(StepOutcome::Died));
+        assert_eq!(played.to_save(), world.to_save());
+    }
+
+    #[test]
+    fn decode_rejects_unknown_turn() {
+        let (replay, _) = record_game(5);
+        let text = replay.encode().replacen("turns ", "turns X", 1);
+        assert!(matches!(Replay::decode(&text), Err(ReplayError::Format(message)) if message.contains("turn")));
+    }
+
+    #[test]
+    fn decode_rejects_wrong_header() {
+        let text = "snake-replay 7\ngrid 15 15\nseed 1 1\nturns ..\n";
+        assert!(matches!(Replay::decode(text), Err(ReplayError::Format(_))));
+    }
+
+    #[test]
+    fn playback_reproduces_the_recorded_game() {
+        let (replay, recorded) = record_game(11);
+        let mut player = ReplayPlayer::new(replay);
+        let mut world = player.world();
+
+        while player.step(&mut world).is_some() {}
+
+        assert!(player.is_finished());
+        assert_eq!(world.to_save(), recorded.to_save());
+    }
+
+    #[test]
+    fn playback_after_restart_uses_recorded_rng_state() {
+        let mut world = World::new(15, 15, 3);
+        world.start();
+        for _ in 0..5 {
+            world.add_food();
+        }
+        world.restart();
+        let replay = Replay::start(&world);
+
+        assert_eq!(replay.world().rng(), world.rng());
+    }
+
+    #[test]
+    fn progress_counts_played_ticks() {
+        let mut replay = Replay::start(&World::new(15, 15, 0));
+        replay.record(None);
+        replay.record(Some(Direction::Down));
+        let mut player = ReplayPlayer::new(replay);
+        let mut world = player.world();
+
+        player.step(&mut world);
+        assert_eq!(player.progress(), (1, 2));
+        player.step(&mut world);
+        assert!(player.step(&mut world).is_none());
+    }
+}
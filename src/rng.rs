@@ -0,0 +1,100 @@
+use rand::RngCore;
+
+/// Small seedable generator (SplitMix64) owned by the [`World`](crate::world::World).
+///
+/// Unlike `rand::rng()` its whole state is a single `u64`, so the same seed
+/// always yields the same food sequence and the state can be written to disk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRng {
+    seed: u64,
+    state: u64,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> GameRng {
+        GameRng { seed, state: seed }
+    }
+
+    /// Resumes a generator mid-sequence, as written by [`GameRng::state`].
+    pub fn from_state(seed: u64, state: u64) -> GameRng {
+        GameRng { seed, state }
+    }
+
+    /// Picks a seed from the OS generator for runs that did not ask for one.
+    pub fn random_seed() -> u64 {
+        rand::random()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_sequence() {
+        let mut a = GameRng::new(42);
+        let mut b = GameRng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn different_seeds_diverge() {
+        let mut a = GameRng::new(1);
+        let mut b = GameRng::new(2);
+        assert_ne!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn seed_is_kept_after_drawing() {
+        let mut rng = GameRng::new(7);
+        rng.next_u64();
+        assert_eq!(rng.seed(), 7);
+    }
+
+    #[test]
+    fn from_state_continues_the_sequence() {
+        let mut original = GameRng::new(9);
+        original.next_u64();
+        let mut resumed = GameRng::from_state(original.seed(), original.state());
+        assert_eq!(original.next_u64(), resumed.next_u64());
+    }
+
+    #[test]
+    fn fill_bytes_handles_partial_chunks() {
+        let mut rng = GameRng::new(3);
+        let mut buf = [0u8; 11];
+        rng.fill_bytes(&mut buf);
+        assert!(buf.iter().any(|b| *b != 0));
+    }
+}
@@ -0,0 +1,446 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::difficulty::Difficulty;
+use crate::food::{Food, FoodKind};
+use crate::level::Level;
+use crate::score::Score;
+use crate::snake::Direction;
+use crate::world::{BoardMode, GameState};
+
+pub const SAVE_FILE: &str = "savegame.txt";
+
+const MAGIC: &str = "snake-save";
+const SAVE_VERSION: u32 = 1;
+
+/// Everything needed to continue a game exactly where it was left.
+///
+/// Stored as a small line-based text file, one `key value...` pair per line,
+/// headed by a version line so older saves can be rejected cleanly. The level
+/// follows a final `level` line in its own [`Level`] format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveGame {
+    pub width: i32,
+    pub height: i32,
+    pub state: GameState,
+    pub seed: u64,
+    pub rng_state: u64,
+    pub tick: u64,
+    pub mode: BoardMode,
+    pub difficulty: Difficulty,
+    pub direction: Direction,
+    pub snake: Vec<(i32, i32)>,
+    pub foods: Vec<Food>,
+    pub growth: u32,
+    pub speed: i32,
+    pub score: Score,
+    pub level: Level,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Version(String),
+    Missing(&'static str),
+    Invalid { key: &'static str, value: String },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "io error: {}", err),
+            SaveError::Version(found) => {
+                write!(f, "unsupported save version {:?}, expected {} {}", found, MAGIC, SAVE_VERSION)
+            }
+            SaveError::Missing(key) => write!(f, "missing entry '{}'", key),
+            SaveError::Invalid { key, value } => write!(f, "invalid value for '{}': {:?}", key, value),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl SaveGame {
+    pub fn encode(&self) -> String {
+        let positions = |cells: &[(i32, i32)]| {
+            cells
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut out = String::new();
+        out.push_str(&format!("{} {}\n", MAGIC, SAVE_VERSION));
+        out.push_str(&format!("grid {} {}\n", self.width, self.height));
+        out.push_str(&format!("state {}\n", state_name(&self.state)));
+        out.push_str(&format!("seed {}\n", self.seed));
+        out.push_str(&format!("rng {}\n", self.rng_state));
+        out.push_str(&format!("tick {}\n", self.tick));
+        out.push_str(&format!("mode {}\n", self.mode.name()));
+        out.push_str(&format!("difficulty {}\n", self.difficulty.name()));
+        out.push_str(&format!("direction {}\n", direction_name(self.direction)));
+        out.push_str(&format!("snake {}\n", positions(&self.snake)));
+        out.push_str(&format!("foods {}\n", foods(&self.foods)));
+        out.push_str(&format!("growth {}\n", self.growth));
+        out.push_str(&format!("speed {}\n", self.speed));
+        let last_eat = self.score.last_eat().map_or("-".to_string(), |tick| tick.to_string());
+        out.push_str(&format!(
+            "score {} {} {}\n",
+            self.score.points(),
+            self.score.combo(),
+            last_eat
+        ));
+        out.push_str("level\n");
+        out.push_str(&self.level.encode());
+        out
+    }
+
+    pub fn decode(text: &str) -> Result<SaveGame, SaveError> {
+        let (text, level) = match text.split_once("\nlevel\n") {
+            Some((head, level)) => (head, Some(level)),
+            None => (text, None),
+        };
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or("").trim();
+        if header != format!("{} {}", MAGIC, SAVE_VERSION) {
+            return Err(SaveError::Version(header.to_string()));
+        }
+
+        let entries: Vec<(&str, &str)> = lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.split_once(' ').unwrap_or((line, "")))
+            .collect();
+        let entry = |key: &'static str| {
+            entries
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.trim())
+                .ok_or(SaveError::Missing(key))
+        };
+
+        let (width, height) = parse_pair(entry("grid")?, ' ', "grid")?;
+        let state = parse_state(entry("state")?)?;
+        let seed = parse_number(entry("seed")?, "seed")?;
+        let rng_state = parse_number(entry("rng")?, "rng")?;
+        let tick = parse_number(entry("tick")?, "tick")?;
+        let mode = entry("mode")?;
+        let mode = BoardMode::from_name(mode).ok_or_else(|| invalid("mode", mode))?;
+        let difficulty = entry("difficulty")?;
+        let difficulty = Difficulty::from_name(difficulty).ok_or_else(|| invalid("difficulty", difficulty))?;
+        let direction = parse_direction(entry("direction")?, "direction")?;
+
+        let snake = entry("snake")?
+            .split_whitespace()
+            .map(|cell| parse_pair(cell, ',', "snake"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if snake.is_empty() {
+            return Err(SaveError::Invalid {
+                key: "snake",
+                value: String::new(),
+            });
+        }
+
+        let foods = entry("foods")?
+            .split_whitespace()
+            .map(parse_food)
+            .collect::<Result<Vec<_>, _>>()?;
+        let growth = parse_number(entry("growth")?, "growth")?;
+        let speed = parse_number(entry("speed")?, "speed")?;
+        let score = parse_score(entry("score")?)?;
+
+        let level = level.ok_or(SaveError::Missing("level"))?;
+        let level = Level::parse(level).map_err(|err| invalid("level", &err.to_string()))?;
+
+        Ok(SaveGame {
+            width,
+            height,
+            state,
+            seed,
+            rng_state,
+            tick,
+            mode,
+            difficulty,
+            direction,
+            snake,
+            foods,
+            growth,
+            speed,
+            score,
+            level,
+        })
+    }
+}
+
+pub fn save_to(path: &Path, save: &SaveGame) -> Result<(), SaveError> {
+    fs::write(path, save.encode())?;
+    Ok(())
+}
+
+pub fn load_from(path: &Path) -> Result<SaveGame, SaveError> {
+    let content = fs::read_to_string(path)?;
+    SaveGame::decode(&content)
+}
+
+/// Foods as `kind:x,y`, with `@tick` appended for food that expires.
+fn foods(foods: &[Food]) -> String {
+    foods
+        .iter()
+        .map(|food| {
+            let (x, y) = food.position();
+            match food.expires_at() {
+                Some(at) => format!("{}:{},{}@{}", food.kind().name(), x, y, at),
+                None => format!("{}:{},{}", food.kind().name(), x, y),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_food(value: &str) -> Result<Food, SaveError> {
+    let (kind, rest) = value.split_once(':').ok_or_else(|| invalid("foods", value))?;
+    let kind = FoodKind::from_name(kind).ok_or_else(|| invalid("foods", value))?;
+    let (cell, expires_at) = match rest.split_once('@') {
+        Some((cell, at)) => (cell, Some(parse_number(at, "foods")?)),
+        None => (rest, None),
+    };
+    let (x, y) = parse_pair(cell, ',', "foods")?;
+    Ok(Food::new(kind, x, y, expires_at))
+}
+
+fn parse_score(value: &str) -> Result<Score, SaveError> {
+    let [points, combo, last_eat] = value.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err(invalid("score", value));
+    };
+    let last_eat = match last_eat {
+        "-" => None,
+        tick => Some(parse_number(tick, "score")?),
+    };
+    Ok(Score::from_parts(
+        parse_number(points, "score")?,
+        parse_number(combo, "score")?,
+        last_eat,
+    ))
+}
+
+fn direction_name(dir: Direction) -> &'static str {
+    match dir {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+fn parse_direction(value: &str, key: &'static str) -> Result<Direction, SaveError> {
+    match value {
+        "up" => Ok(Direction::Up),
+        "down" => Ok(Direction::Down),
+        "left" => Ok(Direction::Left),
+        "right" => Ok(Direction::Right),
+        _ => Err(invalid(key, value)),
+    }
+}
+
+fn state_name(state: &GameState) -> String {
+    match state {
+        GameState::Menu => "menu".to_string(),
+        GameState::Playing => "playing".to_string(),
+        GameState::Paused => "paused".to_string(),
+        GameState::GameOver { final_score } => format!("gameover {}", final_score),
+        GameState::Victory { final_score } => format!("victory {}", final_score),
+    }
+}
+
+fn parse_state(value: &str) -> Result<GameState, SaveError> {
+    match value.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["menu"] => Ok(GameState::Menu),
+        ["playing"] => Ok(GameState::Playing),
+        ["paused"] => Ok(GameState::Paused),
+        ["gameover", score] => Ok(GameState::GameOver {
+            final_score: parse_number(score, "state")?,
+        }),
+        ["victory", score] => Ok(GameState::Victory {
+            final_score: parse_number(score, "state")?,
+        }),
+        _ => Err(invalid("state", value)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, key: &'static str) -> Result<T, SaveError> {
+    value.trim().parse().map_err(|_| invalid(key, value))
+}
+
+fn parse_pair(value: &str, sep: char, key: &'static str) -> Result<(i32, i32), SaveError> {
+    let (a, b) = value.split_once(sep).ok_or_else(|| invalid(key, value))?;
+    Ok((parse_number(a, key)?, parse_number(b, key)?))
+}
+
+fn invalid(key: &'static str, value: &str) -> SaveError {
+    SaveError::Invalid {
+        key,
+        value: value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> SaveGame {
+        SaveGame {
+            width: 15,
+            height: 12,
+            state: GameState::Paused,
+            seed: 42,
+            rng_state: 123_456_789,
+            tick: 17,
+            mode: BoardMode::Wrap,
+            difficulty: Difficulty::Hard,
+            direction: Direction::Down,
+            snake: vec![(5, 4), (5, 3), (4, 3)],
+            foods: vec![
+                Food::new(FoodKind::Normal, 9, 9, None),
+                Food::new(FoodKind::Bonus, 2, 7, Some(40)),
+            ],
+            growth: 2,
+            speed: -1,
+            score: Score::from_parts(140, 3, Some(12)),
+            level: Level::empty(15, 12),
+        }
+    }
+
+    fn cleanup(path: &str) {
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let save = sample();
+        assert_eq!(SaveGame::decode(&save.encode()).unwrap(), save);
+    }
+
+    #[test]
+    fn roundtrip_without_food() {
+        let save = SaveGame {
+            foods: Vec::new(),
+            state: GameState::GameOver { final_score: 7 },
+            ..sample()
+        };
+        assert_eq!(SaveGame::decode(&save.encode()).unwrap(), save);
+    }
+
+    #[test]
+    fn roundtrip_victory() {
+        let save = SaveGame {
+            state: GameState::Victory { final_score: 900 },
+            ..sample()
+        };
+        assert_eq!(SaveGame::decode(&save.encode()).unwrap(), save);
+    }
+
+    #[test]
+    fn roundtrip_without_last_eat() {
+        let save = SaveGame {
+            score: Score::new(),
+            ..sample()
+        };
+        assert_eq!(SaveGame::decode(&save.encode()).unwrap(), save);
+    }
+
+    #[test]
+    fn decode_rejects_unknown_food_kind() {
+        let text = sample().encode().replace("bonus:2,7@40", "cake:2,7");
+        assert!(matches!(
+            SaveGame::decode(&text),
+            Err(SaveError::Invalid { key: "foods", .. })
+        ));
+    }
+
+    #[test]
+    fn roundtrip_keeps_level_walls() {
+        let save = SaveGame {
+            level: Level {
+                walls: vec![(7, 7), (7, 8)],
+                ..Level::empty(15, 12)
+            },
+            ..sample()
+        };
+        assert_eq!(SaveGame::decode(&save.encode()).unwrap(), save);
+    }
+
+    #[test]
+    fn decode_reports_missing_level() {
+        let text = sample().encode();
+        let text = text.split("level\n").next().unwrap();
+        assert!(matches!(SaveGame::decode(text), Err(SaveError::Missing("level"))));
+    }
+
+    #[test]
+    fn decode_rejects_unknown_version() {
+        let text = sample().encode().replacen("snake-save 1", "snake-save 99", 1);
+        assert!(matches!(SaveGame::decode(&text), Err(SaveError::Version(_))));
+    }
+
+    #[test]
+    fn decode_reports_missing_entry() {
+        let text: String = sample()
+            .encode()
+            .lines()
+            .filter(|line| !line.starts_with("rng"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        assert!(matches!(SaveGame::decode(&text), Err(SaveError::Missing("rng"))));
+    }
+
+    #[test]
+    fn decode_reports_invalid_value() {
+        let text = sample().encode().replace("direction down", "direction sideways");
+        assert!(matches!(
+            SaveGame::decode(&text),
+            Err(SaveError::Invalid { key: "direction", .. })
+        ));
+    }
+
+    #[test]
+    fn decode_skips_the_turn_queue_of_older_saves() {
+        // Turns pressed ahead are kept by the keyboard controller now.
+        let text = sample().encode().replace("\nlevel\n", "\nqueue left up\nlevel\n");
+        assert_eq!(SaveGame::decode(&text).unwrap(), sample());
+    }
+
+    #[test]
+    fn decode_rejects_empty_snake() {
+        let text = sample().encode().replace("snake 5,4 5,3 4,3", "snake ");
+        assert!(matches!(
+            SaveGame::decode(&text),
+            Err(SaveError::Invalid { key: "snake", .. })
+        ));
+    }
+
+    #[test]
+    fn save_and_load_file() {
+        let path = "test_save_roundtrip.txt";
+        cleanup(path);
+
+        save_to(Path::new(path), &sample()).unwrap();
+        let loaded = load_from(Path::new(path)).unwrap();
+
+        assert_eq!(loaded, sample());
+        cleanup(path);
+    }
+
+    #[test]
+    fn load_missing_file_is_io_error() {
+        let path = "test_save_missing.txt";
+        cleanup(path);
+        assert!(matches!(load_from(Path::new(path)), Err(SaveError::Io(_))));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::food::Food;
+
+/// Eating again within this many ticks of the last food continues a combo.
+pub const COMBO_WINDOW: u64 = 20;
+
+/// The combo multiplier stops growing here.
+pub const MAX_COMBO: u32 = 5;
+
+/// Percent added to (or, when slowed down, taken off) every award per speed level.
+const SPEED_BONUS_PERCENT: i32 = 10;
+
+/// Points earned in a game, kept apart from the snake's length.
+///
+/// Every food is worth its [`points`](crate::food::FoodKind::points) plus a
+/// time bonus of one point per tick it had left before expiring. The sum is
+/// multiplied by the current combo and scaled by the speed level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Score {
+    points: u32,
+    combo: u32,
+    last_eat: Option<u64>,
+}
+
+impl Score {
+    pub fn new() -> Score {
+        Score::from_parts(0, 1, None)
+    }
+
+    /// Restores a score as written by a save.
+    pub fn from_parts(points: u32, combo: u32, last_eat: Option<u64>) -> Score {
+        Score {
+            points,
+            combo,
+            last_eat,
+        }
+    }
+
+    pub fn points(&self) -> u32 {
+        self.points
+    }
+
+    /// The multiplier applied to the next food if it is eaten in time.
+    pub fn combo(&self) -> u32 {
+        self.combo
+    }
+
+    /// The tick the last food was eaten on.
+    pub fn last_eat(&self) -> Option<u64> {
+        self.last_eat
+    }
+
+    /// Scores `food` eaten on `tick` at `speed_level` and returns the points awarded.
+    pub fn eat(&mut self, food: &Food, tick: u64, speed_level: i32) -> u32 {
+        self.combo = match self.last_eat {
+            Some(last) if tick.saturating_sub(last) <= COMBO_WINDOW => (self.combo + 1).min(MAX_COMBO),
+            _ => 1,
+        };
+        self.last_eat = Some(tick);
+
+        let time_bonus = food.expires_at().map_or(0, |at| at.saturating_sub(tick)) as u32;
+        let speed_percent = (100 + SPEED_BONUS_PERCENT * speed_level).max(0) as u32;
+        let awarded = (food.kind().points() + time_bonus) * self.combo * speed_percent / 100;
+        self.points += awarded;
+        awarded
+    }
+}
+
+impl Default for Score {
+    fn default() -> Score {
+        Score::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::food::FoodKind;
+
+    fn normal() -> Food {
+        Food::new(FoodKind::Normal, 1, 1, None)
+    }
+
+    #[test]
+    fn new_score_is_zero() {
+        let score = Score::new();
+        assert_eq!(score.points(), 0);
+        assert_eq!(score.combo(), 1);
+    }
+
+    #[test]
+    fn food_awards_its_points() {
+        let mut score = Score::new();
+        assert_eq!(score.eat(&normal(), 5, 0), FoodKind::Normal.points());
+        assert_eq!(score.points(), FoodKind::Normal.points());
+    }
+
+    #[test]
+    fn quick_eats_build_a_combo() {
+        let mut score = Score::new();
+        score.eat(&normal(), 10, 0);
+        assert_eq!(score.eat(&normal(), 10 + COMBO_WINDOW, 0), 2 * FoodKind::Normal.points());
+        assert_eq!(score.combo(), 2);
+    }
+
+    #[test]
+    fn slow_eats_reset_the_combo() {
+        let mut score = Score::new();
+        score.eat(&normal(), 10, 0);
+        score.eat(&normal(), 11, 0);
+        score.eat(&normal(), 12 + COMBO_WINDOW, 0);
+        assert_eq!(score.combo(), 1);
+    }
+
+    #[test]
+    fn combo_is_capped() {
+        let mut score = Score::new();
+        for tick in 0..20 {
+            score.eat(&normal(), tick, 0);
+        }
+        assert_eq!(score.combo(), MAX_COMBO);
+    }
+
+    #[test]
+    fn expiring_food_adds_a_time_bonus() {
+        let mut score = Score::new();
+        let bonus = Food::new(FoodKind::Bonus, 1, 1, Some(30));
+        assert_eq!(score.eat(&bonus, 20, 0), FoodKind::Bonus.points() + 10);
+    }
+
+    #[test]
+    fn speed_level_scales_the_award() {
+        let mut fast = Score::new();
+        let mut slow = Score::new();
+        assert!(fast.eat(&normal(), 1, 2) > slow.eat(&normal(), 1, -2));
+    }
+}
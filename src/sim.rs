@@ -0,0 +1,415 @@
+use crate::controller::Controller;
+use crate::world::{GameState, World};
+
+/// How a simulated game ended.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Ending {
+    /// Ran into the edge of the board.
+    Border,
+    /// Ran into an interior wall.
+    Wall,
+    /// Ran into its own body.
+    Body,
+    /// Filled the board.
+    Victory,
+    /// Still alive when the tick limit ran out.
+    OutOfTicks,
+}
+
+impl Ending {
+    pub const ALL: [Ending; 5] = [
+        Ending::Border,
+        Ending::Wall,
+        Ending::Body,
+        Ending::Victory,
+        Ending::OutOfTicks,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ending::Border => "border",
+            Ending::Wall => "wall",
+            Ending::Body => "body",
+            Ending::Victory => "victory",
+            Ending::OutOfTicks => "out_of_ticks",
+        }
+    }
+}
+
+/// The outcome of one simulated game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameResult {
+    pub seed: u64,
+    pub width: i32,
+    pub height: i32,
+    pub score: u32,
+    pub length: usize,
+    pub ticks: u64,
+    pub ending: Ending,
+}
+
+/// Plays `world` to the end with `controller` steering, at most `max_ticks`
+/// ticks, through the same rules the window runs.
+pub fn play(mut world: World, controller: &mut dyn Controller, max_ticks: u64) -> GameResult {
+    world.start();
+    while *world.state() == GameState::Playing && world.tick() < max_ticks {
+        let turn = controller.next_turn(&world.view());
+        world.step_with(turn);
+    }
+
+    let ending = ending(&world).unwrap_or(Ending::OutOfTicks);
+    GameResult {
+        seed: world.seed(),
+        width: world.grid().width,
+        height: world.grid().height,
+        score: world.score().points(),
+        length: world.snake().len(),
+        ticks: world.tick(),
+        ending,
+    }
+}
+
+/// How the game in `world` ended, `None` while it is still going.
+pub fn ending(world: &World) -> Option<Ending> {
+    match world.state() {
+        GameState::Victory { .. } => Some(Ending::Victory),
+        GameState::GameOver { .. } => Some(crash_site(world)),
+        GameState::Menu | GameState::Playing | GameState::Paused => None,
+    }
+}
+
+/// What the snake of a finished game ran into: the dead snake stays where it
+/// was, so the fatal cell is one step ahead along its last turn.
+fn crash_site(world: &World) -> Ending {
+    let (x, y) = world.snake().next_head(world.last_turn());
+    if !world.grid().is_inside(x, y) {
+        Ending::Border
+    } else if world.is_wall(x, y) {
+        Ending::Wall
+    } else {
+        Ending::Body
+    }
+}
+
+/// Aggregate statistics over the games played on one board size.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub width: i32,
+    pub height: i32,
+    pub games: usize,
+    pub mean_score: f64,
+    pub median_score: f64,
+    pub best_score: u32,
+    pub mean_ticks: f64,
+    pub median_ticks: f64,
+    pub mean_length: f64,
+    /// How many games ended each way, in [`Ending::ALL`] order.
+    pub endings: Vec<(Ending, usize)>,
+}
+
+impl Summary {
+    /// Sums up `results`, one summary per board size in the order the sizes
+    /// first appear.
+    pub fn by_size(results: &[GameResult]) -> Vec<Summary> {
+        let mut sizes: Vec<(i32, i32)> = Vec::new();
+        for result in results {
+            if !sizes.contains(&(result.width, result.height)) {
+                sizes.push((result.width, result.height));
+            }
+        }
+        sizes
+            .into_iter()
+            .map(|(width, height)| {
+                let games: Vec<&GameResult> = results
+                    .iter()
+                    .filter(|result| (result.width, result.height) == (width, height))
+                    .collect();
+                Summary::of(width, height, &games)
+            })
+            .collect()
+    }
+
+    fn of(width: i32, height: i32, games: &[&GameResult]) -> Summary {
+        let scores: Vec<f64> = games.iter().map(|game| game.score as f64).collect();
+        let ticks: Vec<f64> = games.iter().map(|game| game.ticks as f64).collect();
+        let lengths: Vec<f64> = games.iter().map(|game| game.length as f64).collect();
+        Summary {
+            width,
+            height,
+            games: games.len(),
+            mean_score: mean(&scores),
+            median_score: median(&scores),
+            best_score: games.iter().map(|game| game.score).max().unwrap_or(0),
+            mean_ticks: mean(&ticks),
+            median_ticks: median(&ticks),
+            mean_length: mean(&lengths),
+            endings: Ending::ALL
+                .into_iter()
+                .map(|ending| (ending, games.iter().filter(|game| game.ending == ending).count()))
+                .collect(),
+        }
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// The middle value, or the mean of the two middle values for an even count.
+fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+/// How the statistics are printed.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Text,
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Text, Format::Csv, Format::Json];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Csv => "csv",
+            Format::Json => "json",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        Format::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    /// Renders the summaries of a run with `bot`.
+    pub fn render(&self, bot: &str, summaries: &[Summary]) -> String {
+        match self {
+            Format::Text => render_text(bot, summaries),
+            Format::Csv => render_csv(bot, summaries),
+            Format::Json => render_json(bot, summaries),
+        }
+    }
+}
+
+fn render_text(bot: &str, summaries: &[Summary]) -> String {
+    let mut out = String::new();
+    for summary in summaries {
+        out.push_str(&format!(
+            "{} on {}x{}: {} games\n",
+            bot, summary.width, summary.height, summary.games
+        ));
+        out.push_str(&format!(
+            "  score   mean {:.1}  median {:.1}  best {}\n",
+            summary.mean_score, summary.median_score, summary.best_score
+        ));
+        out.push_str(&format!(
+            "  ticks   mean {:.1}  median {:.1}\n",
+            summary.mean_ticks, summary.median_ticks
+        ));
+        out.push_str(&format!("  length  mean {:.1}\n", summary.mean_length));
+        let endings: Vec<String> = summary
+            .endings
+            .iter()
+            .map(|(ending, count)| format!("{} {}", ending.name(), count))
+            .collect();
+        out.push_str(&format!("  endings {}\n", endings.join(", ")));
+    }
+    out
+}
+
+fn render_csv(bot: &str, summaries: &[Summary]) -> String {
+    let mut out = String::from(
+        "bot,width,height,games,mean_score,median_score,best_score,mean_ticks,median_ticks,mean_length",
+    );
+    for ending in Ending::ALL {
+        out.push(',');
+        out.push_str(ending.name());
+    }
+    out.push('\n');
+    for summary in summaries {
+        out.push_str(&format!(
+            "{},{},{},{},{:.3},{:.3},{},{:.3},{:.3},{:.3}",
+            bot,
+            summary.width,
+            summary.height,
+            summary.games,
+            summary.mean_score,
+            summary.median_score,
+            summary.best_score,
+            summary.mean_ticks,
+            summary.median_ticks,
+            summary.mean_length
+        ));
+        for (_, count) in &summary.endings {
+            out.push_str(&format!(",{}", count));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn render_json(bot: &str, summaries: &[Summary]) -> String {
+    let sizes: Vec<String> = summaries
+        .iter()
+        .map(|summary| {
+            let endings: Vec<String> = summary
+                .endings
+                .iter()
+                .map(|(ending, count)| format!("\"{}\":{}", ending.name(), count))
+                .collect();
+            format!(
+                "{{\"width\":{},\"height\":{},\"games\":{},\"mean_score\":{:.3},\"median_score\":{:.3},\
+                 \"best_score\":{},\"mean_ticks\":{:.3},\"median_ticks\":{:.3},\"mean_length\":{:.3},\
+                 \"endings\":{{{}}}}}",
+                summary.width,
+                summary.height,
+                summary.games,
+                summary.mean_score,
+                summary.median_score,
+                summary.best_score,
+                summary.mean_ticks,
+                summary.median_ticks,
+                summary.mean_length,
+                endings.join(",")
+            )
+        })
+        .collect();
+    format!("{{\"bot\":{:?},\"sizes\":[{}]}}\n", bot, sizes.join(","))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bots::{by_name, GreedyBot};
+    use crate::controller::KeyboardController;
+    use crate::level::Level;
+    use crate::rng::GameRng;
+
+    fn result(score: u32, ticks: u64, ending: Ending) -> GameResult {
+        GameResult {
+            seed: 0,
+            width: 15,
+            height: 15,
+            score,
+            length: 3,
+            ticks,
+            ending,
+        }
+    }
+
+    #[test]
+    fn plays_a_game_to_the_end() {
+        let result = play(World::new(15, 15, 7), &mut GreedyBot, 10_000);
+        assert_ne!(result.ending, Ending::OutOfTicks);
+        assert_eq!((result.seed, result.width, result.height), (7, 15, 15));
+        assert!(result.ticks > 0);
+    }
+
+    #[test]
+    fn same_seed_plays_the_same_game() {
+        let run = || play(World::new(12, 12, 4), by_name("random", 4).unwrap().as_mut(), 10_000);
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn stops_at_the_tick_limit() {
+        let result = play(World::new(15, 15, 0), by_name("autopilot", 0).unwrap().as_mut(), 5);
+        assert_eq!(result.ending, Ending::OutOfTicks);
+        assert_eq!(result.ticks, 5);
+    }
+
+    #[test]
+    fn tells_walls_from_the_border() {
+        // Without key presses the snake runs straight ahead, here right into
+        // an interior wall.
+        let mut idle = KeyboardController::new();
+        let level = Level::parse("map\n########\n#..@#..#\n#......#\n#..*...#\n########\n").unwrap();
+        let world = World::with_level(level, GameRng::new(0));
+        assert_eq!(play(world, &mut idle, 100).ending, Ending::Wall);
+        assert_eq!(play(World::new(15, 15, 0), &mut idle, 100).ending, Ending::Border);
+    }
+
+    #[test]
+    fn hamilton_wins_on_a_small_board() {
+        let result = play(World::new(6, 6, 3), by_name("hamilton", 3).unwrap().as_mut(), 10_000);
+        assert_eq!(result.ending, Ending::Victory);
+        assert_eq!(result.length, 16);
+    }
+
+    #[test]
+    fn summary_takes_mean_and_median() {
+        let results = [
+            result(10, 100, Ending::Body),
+            result(20, 200, Ending::Border),
+            result(60, 300, Ending::Body),
+            result(30, 400, Ending::Victory),
+        ];
+        let summaries = Summary::by_size(&results);
+        assert_eq!(summaries.len(), 1);
+        let summary = &summaries[0];
+        assert_eq!(summary.games, 4);
+        assert_eq!(summary.mean_score, 30.0);
+        assert_eq!(summary.median_score, 25.0);
+        assert_eq!(summary.best_score, 60);
+        assert_eq!(summary.median_ticks, 250.0);
+        assert_eq!(summary.endings[0], (Ending::Border, 1));
+        assert_eq!(summary.endings[2], (Ending::Body, 2));
+    }
+
+    #[test]
+    fn summaries_are_split_by_board_size() {
+        let mut small = result(10, 100, Ending::Body);
+        small.width = 10;
+        let results = [result(10, 100, Ending::Body), small, result(30, 100, Ending::Body)];
+        let summaries = Summary::by_size(&results);
+        assert_eq!(
+            summaries.iter().map(|summary| (summary.width, summary.games)).collect::<Vec<_>>(),
+            vec![(15, 2), (10, 1)]
+        );
+    }
+
+    #[test]
+    fn csv_has_a_header_and_a_row_per_size() {
+        let summaries = Summary::by_size(&[result(10, 100, Ending::Body)]);
+        let csv = Format::Csv.render("greedy", &summaries);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("border,wall,body,victory,out_of_ticks"));
+        assert_eq!(
+            lines[1],
+            "greedy,15,15,1,10.000,10.000,10,100.000,100.000,3.000,0,0,1,0,0"
+        );
+    }
+
+    #[test]
+    fn json_lists_endings_by_name() {
+        let summaries = Summary::by_size(&[result(10, 100, Ending::Victory)]);
+        let json = Format::Json.render("hamilton", &summaries);
+        assert!(json.starts_with("{\"bot\":\"hamilton\",\"sizes\":[{\"width\":15,"));
+        assert!(json.contains("\"endings\":{\"border\":0,\"wall\":0,\"body\":0,\"victory\":1,\"out_of_ticks\":0}"));
+    }
+
+    #[test]
+    fn format_names_roundtrip() {
+        for format in Format::ALL {
+            assert_eq!(Format::from_name(format.name()), Some(format));
+        }
+        assert_eq!(Format::from_name("xml"), None);
+    }
+}
@@ -1,13 +1,5 @@
 use std::collections::{HashSet, VecDeque};
 
-use piston_window as pw;
-
-use crate::draw::draw_block;
-#[cfg(feature = "debug_draw")]
-use crate::draw::{to_coord, BLOCK_SIZE};
-
-const SNAKE_COLOR: pw::graphics::types::Color = [0.00, 0.80, 0.00, 1.0];
-
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Direction {
     Up,
@@ -36,6 +28,26 @@ impl Direction {
     }
 }
 
+/// Inclusive range of cells a wrapping snake moves within. Leaving it on one
+/// side brings the head back in on the opposite side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WrapBounds {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
+impl WrapBounds {
+    pub fn wrap(&self, x: i32, y: i32) -> (i32, i32) {
+        let wrap_axis = |v: i32, min: i32, max: i32| min + (v - min).rem_euclid(max - min + 1);
+        (
+            wrap_axis(x, self.min_x, self.max_x),
+            wrap_axis(y, self.min_y, self.max_y),
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Block {
     x: i32,
@@ -47,6 +59,7 @@ pub struct Snake {
     body: VecDeque<Block>,
     tail: Option<Block>,
     occupied: HashSet<Block>,
+    wrap: Option<WrapBounds>,
 }
 
 impl Snake {
@@ -63,35 +76,28 @@ impl Snake {
             body,
             tail: None,
             occupied,
+            wrap: None,
         }
     }
 
-    pub fn draw(&self, con: &pw::graphics::Context, g: &mut pw::wgpu_graphics::WgpuGraphics) {
-        for block in &self.body {
-            draw_block(SNAKE_COLOR, block.x, block.y, con, g);
+    /// Rebuilds a snake from its segments (head first) and heading,
+    /// e.g. when resuming a saved game.
+    pub fn from_parts(body: &[(i32, i32)], direction: Direction) -> Snake {
+        let body: VecDeque<Block> = body.iter().map(|&(x, y)| Block { x, y }).collect();
+        let occupied: HashSet<Block> = body.iter().copied().collect();
+
+        Snake {
+            direction,
+            body,
+            tail: None,
+            occupied,
+            wrap: None,
         }
     }
 
-    #[cfg(feature = "debug_draw")]
-    pub fn draw_direction_indicator(
-        &self,
-        con: &pw::graphics::Context,
-        g: &mut pw::wgpu_graphics::WgpuGraphics,
-        color: pw::graphics::types::Color,
-    ) {
-        let (hx, hy) = self.head_position();
-        let x = to_coord(hx);
-        let y = to_coord(hy);
-        let thickness = BLOCK_SIZE * 0.2;
-
-        let rect = match self.direction {
-            Direction::Up => [x, y, BLOCK_SIZE, thickness],
-            Direction::Down => [x, y + BLOCK_SIZE - thickness, BLOCK_SIZE, thickness],
-            Direction::Left => [x, y, thickness, BLOCK_SIZE],
-            Direction::Right => [x + BLOCK_SIZE - thickness, y, thickness, BLOCK_SIZE],
-        };
-
-        pw::graphics::rectangle(color, rect, con.transform, g);
+    /// Makes the snake wrap around `bounds` instead of leaving them.
+    pub fn set_wrap(&mut self, bounds: Option<WrapBounds>) {
+        self.wrap = bounds;
     }
 
     pub fn head_position(&self) -> (i32, i32) {
@@ -104,12 +110,10 @@ impl Snake {
             self.direction = d;
         }
 
-        let (last_x, last_y): (i32, i32) = self.head_position();
-
-        let direction_arr = self.direction.as_array();
+        let (next_x, next_y) = self.next_head(None);
         let new_block_v2 = Block {
-            x: last_x + direction_arr[0],
-            y: last_y + direction_arr[1],
+            x: next_x,
+            y: next_y,
         };
 
         self.body.push_front(new_block_v2);
@@ -131,7 +135,11 @@ impl Snake {
 
         let moving_direction_arr: [i32; 2] = moving_dir.as_array();
         let (hx, hy) = self.head_position();
-        (hx + moving_direction_arr[0], hy + moving_direction_arr[1])
+        let next = (hx + moving_direction_arr[0], hy + moving_direction_arr[1]);
+        match self.wrap {
+            Some(bounds) => bounds.wrap(next.0, next.1),
+            None => next,
+        }
     }
 
     pub fn restore_tail(&mut self) {
@@ -140,6 +148,22 @@ impl Snake {
         self.occupied.insert(blk);
     }
 
+    /// Drops the last segment, keeping at least the head.
+    pub fn remove_tail(&mut self) -> bool {
+        if self.body.len() <= 1 {
+            return false;
+        }
+        let blk = self.body.pop_back().unwrap();
+        self.occupied.remove(&blk);
+        self.tail = Some(blk);
+        true
+    }
+
+    /// Whether any segment, the head included, is on (x, y).
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.occupied.contains(&Block { x, y })
+    }
+
     /// runs in O(1) by using a hashset
     pub fn overlap_tail(&self, x: i32, y: i32) -> bool {
         let head = self.body.front().unwrap();
@@ -154,10 +178,28 @@ impl Snake {
         self.body.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
     pub fn body_positions(&self) -> Vec<(i32, i32)> {
         self.body.iter().map(|block| (block.x, block.y)).collect()
     }
 
+    /// Where each segment of [`Snake::body_positions`] was before the last move.
+    /// Before the first move every segment is reported at its current cell.
+    pub fn previous_positions(&self) -> Vec<(i32, i32)> {
+        let Some(tail) = self.tail else {
+            return self.body_positions();
+        };
+        self.body
+            .iter()
+            .skip(1)
+            .chain(std::iter::once(&tail))
+            .map(|block| (block.x, block.y))
+            .collect()
+    }
+
     /// Returns how many direction changes occur along the snake body.
     pub fn corner_count(&self) -> usize {
         let parts: Vec<_> = self.body.iter().collect();
@@ -230,8 +272,34 @@ mod test {
         }
     }
 
+    mod wrap_bounds {
+        use crate::snake::WrapBounds;
+
+        const BOUNDS: WrapBounds = WrapBounds {
+            min_x: 1,
+            min_y: 1,
+            max_x: 13,
+            max_y: 13,
+        };
+
+        #[test]
+        fn wrap_keeps_cells_inside() {
+            assert_eq!(BOUNDS.wrap(5, 7), (5, 7));
+        }
+
+        #[test]
+        fn wrap_moves_past_max_to_min() {
+            assert_eq!(BOUNDS.wrap(14, 14), (1, 1));
+        }
+
+        #[test]
+        fn wrap_moves_past_min_to_max() {
+            assert_eq!(BOUNDS.wrap(0, 0), (13, 13));
+        }
+    }
+
     mod snake {
-        use crate::snake::{Direction, Snake};
+        use crate::snake::{Direction, Snake, WrapBounds};
 
         #[test]
         fn new_creates_snake_with_length_3() {
@@ -285,6 +353,24 @@ mod test {
             assert_eq!(snake.len(), len_before + 1);
         }
 
+        #[test]
+        fn remove_tail_shortens_and_can_be_restored() {
+            let mut snake = Snake::new(2, 2);
+            assert!(snake.remove_tail());
+            assert_eq!(snake.len(), 2);
+            assert!(!snake.overlap_tail(2, 2));
+
+            snake.restore_tail();
+            assert_eq!(snake.body_positions(), vec![(4, 2), (3, 2), (2, 2)]);
+        }
+
+        #[test]
+        fn remove_tail_keeps_the_head() {
+            let mut snake = Snake::from_parts(&[(1, 1)], Direction::Right);
+            assert!(!snake.remove_tail());
+            assert_eq!(snake.len(), 1);
+        }
+
         #[test]
         fn next_head_predicts_correctly_without_direction() {
             let snake = Snake::new(2, 2);
@@ -321,6 +407,14 @@ mod test {
             assert!(!snake.overlap_tail(4, 2));
         }
 
+        #[test]
+        fn contains_includes_head() {
+            let snake = Snake::new(2, 2);
+            assert!(snake.contains(4, 2));
+            assert!(snake.contains(2, 2));
+            assert!(!snake.contains(5, 2));
+        }
+
         #[test]
         fn overlap_tail_detects_tail_segment() {
             let snake = Snake::new(2, 2);
@@ -328,6 +422,63 @@ mod test {
             assert!(snake.overlap_tail(2, 2));
         }
 
+        #[test]
+        fn previous_positions_match_body_before_first_move() {
+            let snake = Snake::new(2, 2);
+            assert_eq!(snake.previous_positions(), snake.body_positions());
+        }
+
+        #[test]
+        fn previous_positions_trail_by_one_move() {
+            let mut snake = Snake::new(2, 2);
+            let before = snake.body_positions();
+            snake.move_forward(None);
+            assert_eq!(snake.previous_positions(), before);
+        }
+
+        #[test]
+        fn previous_positions_keep_restored_tail_in_place() {
+            let mut snake = Snake::new(2, 2);
+            snake.move_forward(None);
+            snake.restore_tail();
+            assert_eq!(snake.previous_positions(), vec![(4, 2), (3, 2), (2, 2), (2, 2)]);
+        }
+
+        #[test]
+        fn from_parts_restores_body_and_direction() {
+            let snake = Snake::from_parts(&[(5, 5), (5, 6), (4, 6)], Direction::Up);
+            assert_eq!(snake.body_positions(), vec![(5, 5), (5, 6), (4, 6)]);
+            assert_eq!(snake.head_direction(), Direction::Up);
+            assert!(snake.overlap_tail(4, 6));
+        }
+
+        #[test]
+        fn next_head_wraps_around_bounds() {
+            let mut snake = Snake::new(2, 2);
+            snake.set_wrap(Some(WrapBounds {
+                min_x: 1,
+                min_y: 1,
+                max_x: 4,
+                max_y: 4,
+            }));
+            assert_eq!(snake.next_head(None), (1, 2));
+            assert_eq!(snake.next_head(Some(Direction::Up)), (4, 1));
+        }
+
+        #[test]
+        fn move_forward_wraps_head() {
+            let mut snake = Snake::new(2, 2);
+            snake.set_wrap(Some(WrapBounds {
+                min_x: 1,
+                min_y: 1,
+                max_x: 4,
+                max_y: 4,
+            }));
+            snake.move_forward(None);
+            assert_eq!(snake.head_position(), (1, 2));
+            assert_eq!(snake.body_positions(), vec![(1, 2), (4, 2), (3, 2)]);
+        }
+
         #[test]
         fn move_in_all_directions() {
             let mut snake = Snake::new(5, 5);
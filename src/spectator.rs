@@ -19,10 +19,26 @@ pub struct GameSnapshot {
     pub width: i32,
     pub height: i32,
     pub snake: Vec<(i32, i32)>,
-    pub food: Option<(i32, i32)>,
-    pub score: usize,
+    /// The other snakes of a two-player match, empty when playing alone.
+    pub rivals: Vec<Vec<(i32, i32)>>,
+    pub foods: Vec<FoodSnapshot>,
+    pub score: u32,
+    pub length: usize,
     pub state: String,
     pub tick: u64,
+    pub seed: u64,
+    pub mode: String,
+    pub difficulty: String,
+    pub move_period: f64,
+    pub walls: Vec<(i32, i32)>,
+}
+
+#[cfg(feature = "spectator")]
+#[derive(Serialize)]
+pub struct FoodSnapshot {
+    pub x: i32,
+    pub y: i32,
+    pub kind: String,
 }
 
 #[cfg(feature = "spectator")]
@@ -73,9 +89,12 @@ pub fn start(addr: &str) -> SpectatorHandle {
     SpectatorHandle { tx }
 }
 
+/// Serves the spectator page, pointed at the WebSocket on `ws_port`.
 #[cfg(feature = "spectator")]
-pub fn start_http(addr: &str) {
+pub fn start_http(addr: &str, ws_port: u16) {
     let addr = addr.to_string();
+    let page = include_str!("../spectator.html")
+        .replace("const WS_PORT = 9001;", &format!("const WS_PORT = {};", ws_port));
     thread::spawn(move || {
         let listener = TcpListener::bind(&addr).expect("Failed to bind spectator HTTP socket");
         for stream in listener.incoming() {
@@ -94,7 +113,7 @@ pub fn start_http(addr: &str) {
                 .unwrap_or("/");
 
             let body = match path {
-                "/" | "/spectator.html" => include_str!("../spectator.html"),
+                "/" | "/spectator.html" => page.as_str(),
                 "/health" => "ok",
                 _ => "not found",
             };
@@ -113,7 +132,7 @@ pub fn start_http(addr: &str) {
 
             let response = format!(
                 "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
-                body.as_bytes().len()
+                body.len()
             );
             let _ = stream.write_all(response.as_bytes());
         }
@@ -0,0 +1,107 @@
+/// Upper bound on logical steps per update, so a very long frame (window drag,
+/// breakpoint) does not make the snake teleport across the board.
+const MAX_STEPS_PER_UPDATE: u32 = 8;
+
+/// Fixed-timestep accumulator: turns variable frame times into a whole number
+/// of logical game ticks of exactly `period` seconds each.
+#[derive(Clone, Debug)]
+pub struct TickScheduler {
+    period: f64,
+    accumulator: f64,
+}
+
+impl TickScheduler {
+    pub fn new(period: f64) -> TickScheduler {
+        TickScheduler {
+            period,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn period(&self) -> f64 {
+        self.period
+    }
+
+    pub fn set_period(&mut self, period: f64) {
+        self.period = period;
+    }
+
+    /// Adds `delta_time` seconds and returns how many ticks are now due.
+    pub fn advance(&mut self, delta_time: f64) -> u32 {
+        self.accumulator += delta_time;
+
+        let mut steps = 0;
+        while self.accumulator >= self.period && steps < MAX_STEPS_PER_UPDATE {
+            self.accumulator -= self.period;
+            steps += 1;
+        }
+
+        if steps == MAX_STEPS_PER_UPDATE {
+            self.accumulator = self.accumulator.min(self.period);
+        }
+        steps
+    }
+
+    /// How far the clock is into the current tick, from 0.0 to 1.0.
+    /// Front ends use it to interpolate between the last two world states.
+    pub fn alpha(&self) -> f64 {
+        (self.accumulator / self.period).clamp(0.0, 1.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn no_tick_before_period() {
+        let mut scheduler = TickScheduler::new(0.3);
+        assert_eq!(scheduler.advance(0.2), 0);
+    }
+
+    #[test]
+    fn small_frames_add_up_to_a_tick() {
+        let mut scheduler = TickScheduler::new(0.25);
+        assert_eq!(scheduler.advance(0.125), 0);
+        assert_eq!(scheduler.advance(0.125), 1);
+    }
+
+    #[test]
+    fn long_frame_runs_several_ticks() {
+        let mut scheduler = TickScheduler::new(0.25);
+        assert_eq!(scheduler.advance(1.0), 4);
+    }
+
+    #[test]
+    fn remainder_is_carried_over() {
+        let mut scheduler = TickScheduler::new(0.25);
+        assert_eq!(scheduler.advance(0.375), 1);
+        assert_eq!(scheduler.advance(0.125), 1);
+    }
+
+    #[test]
+    fn steps_are_capped_per_update() {
+        let mut scheduler = TickScheduler::new(0.1);
+        assert_eq!(scheduler.advance(100.0), MAX_STEPS_PER_UPDATE);
+        assert!(scheduler.advance(0.0) <= 1);
+    }
+
+    #[test]
+    fn alpha_reports_progress_into_tick() {
+        let mut scheduler = TickScheduler::new(0.5);
+        scheduler.advance(0.25);
+        assert_eq!(scheduler.alpha(), 0.5);
+    }
+
+    #[test]
+    fn reset_clears_accumulator() {
+        let mut scheduler = TickScheduler::new(0.5);
+        scheduler.advance(0.4);
+        scheduler.reset();
+        assert_eq!(scheduler.alpha(), 0.0);
+    }
+}
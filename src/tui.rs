@@ -0,0 +1,235 @@
+use std::io::{self, Write};
+use std::panic;
+use std::time::{Duration, Instant};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use piston_window as pw;
+use snake::world::BoardMode;
+
+use crate::game::{Game, Scene};
+
+/// How long to wait for input between updates, about 60 frames a second.
+const FRAME: Duration = Duration::from_millis(16);
+/// A cell is two columns wide so the board comes out roughly square.
+const CELL: &str = "██";
+const WRAP_CELL: &str = "░░";
+const EMPTY_CELL: &str = "  ";
+const BORDER_COLOR: Color = Color::Grey;
+const WRAP_BORDER_COLOR: Color = Color::DarkGrey;
+const TEXT_COLOR: Color = Color::White;
+/// Rows taken by the HUD above the board.
+const HUD_ROWS: usize = 3;
+
+/// Plays in the terminal instead of a window, until the quit key (Esc by
+/// default) or Ctrl-C. The terminal is put back the way it was when play
+/// ends, including on an error or a panic.
+pub fn run(game: &mut Game) -> io::Result<()> {
+    let mut out = io::stdout();
+    let _screen = Screen::enter(&mut out)?;
+    play(game, &mut out)
+}
+
+/// Raw mode and the alternate screen, left again when dropped.
+struct Screen;
+
+impl Screen {
+    fn enter(out: &mut impl Write) -> io::Result<Screen> {
+        // The panic message would be lost on the alternate screen, so the
+        // terminal is restored before it is printed.
+        let print_panic = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            print_panic(info);
+        }));
+        terminal::enable_raw_mode()?;
+        let screen = Screen;
+        execute!(out, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Leaves raw mode and the alternate screen. Doing it twice is harmless.
+fn restore() {
+    let _ = execute!(io::stdout(), ResetColor, Show, LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+fn play(game: &mut Game, out: &mut impl Write) -> io::Result<()> {
+    let mut last_frame = Vec::new();
+    let mut last_update = Instant::now();
+    let mut board = game.board_size();
+    loop {
+        // A smaller board from the menu would leave the old one behind.
+        if game.board_size() != board {
+            board = game.board_size();
+            execute!(out, Clear(ClearType::All))?;
+            last_frame.clear();
+        }
+        let frame = render(&game.scene())?;
+        // Only changed frames go out, which keeps the game usable over SSH.
+        if frame != last_frame {
+            out.write_all(&frame)?;
+            out.flush()?;
+            last_frame = frame;
+        }
+
+        let deadline = last_update + FRAME;
+        while event::poll(deadline.saturating_duration_since(Instant::now()))? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if is_interrupt(&key) {
+                        return Ok(());
+                    }
+                    if let Some(key) = piston_key(key.code) {
+                        game.key_pressed(key);
+                        if game.wants_to_quit() {
+                            return Ok(());
+                        }
+                    }
+                }
+                Event::Resize(..) => {
+                    execute!(out, Clear(ClearType::All))?;
+                    last_frame.clear();
+                }
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        game.update(now.duration_since(last_update).as_secs_f64());
+        last_update = now;
+    }
+}
+
+/// Ctrl-C always quits, whatever the key map says.
+fn is_interrupt(key: &KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c')
+}
+
+/// The piston key the window would have reported, so both front ends share
+/// `Game::key_pressed` and the key map.
+fn piston_key(code: KeyCode) -> Option<pw::Key> {
+    let key = match code {
+        KeyCode::Up => pw::Key::Up,
+        KeyCode::Down => pw::Key::Down,
+        KeyCode::Left => pw::Key::Left,
+        KeyCode::Right => pw::Key::Right,
+        KeyCode::Enter => pw::Key::Return,
+        KeyCode::Esc => pw::Key::Escape,
+        KeyCode::Backspace => pw::Key::Backspace,
+        KeyCode::Tab => pw::Key::Tab,
+        // Piston numbers F1 to F12 from 0x4000003A, like SDL.
+        KeyCode::F(n @ 1..=12) => pw::Key::from(0x4000_0039 + n as u32),
+        // Printable keys are numbered by their unshifted character.
+        KeyCode::Char(c) if c.is_ascii() => pw::Key::from(c.to_ascii_lowercase() as u32),
+        _ => return None,
+    };
+    (key != pw::Key::Unknown).then_some(key)
+}
+
+fn rgb(color: pw::graphics::types::Color) -> Color {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::Rgb {
+        r: channel(color[0]),
+        g: channel(color[1]),
+        b: channel(color[2]),
+    }
+}
+
+/// Draws the HUD and the board into a buffer of terminal commands.
+fn render(scene: &Scene) -> io::Result<Vec<u8>> {
+    let (width, height) = (scene.grid.width, scene.grid.height);
+    let mut cells: Vec<Option<(&str, Color)>> = vec![None; (width * height) as usize];
+    let mut put = |(x, y): (i32, i32), cell: (&'static str, Color)| {
+        if x >= 0 && y >= 0 && x < width && y < height {
+            cells[(y * width + x) as usize] = Some(cell);
+        }
+    };
+
+    for x in 0..width {
+        for y in 0..height {
+            if x != 0 && y != 0 && x != width - 1 && y != height - 1 {
+                continue;
+            }
+            match scene.mode {
+                BoardMode::Walls => put((x, y), (CELL, BORDER_COLOR)),
+                BoardMode::Wrap if (x + y) % 2 == 0 => put((x, y), (WRAP_CELL, WRAP_BORDER_COLOR)),
+                BoardMode::Wrap => {}
+            }
+        }
+    }
+    for &wall in scene.walls {
+        put(wall, (CELL, BORDER_COLOR));
+    }
+    for food in scene.foods {
+        put(food.position(), (CELL, rgb(scene.palette.food(food.kind()))));
+    }
+    for (snake, color) in &scene.snakes {
+        for cell in snake.body_positions() {
+            put(cell, (CELL, rgb(*color)));
+        }
+    }
+
+    let mut frame = Vec::new();
+    let board_columns = (width * 2) as usize;
+    let left = scene.hud.left_column();
+    let right = scene.hud.right_column();
+    for row in 0..HUD_ROWS {
+        let left = left.get(row).map(String::as_str).unwrap_or("");
+        let right = right.get(row).map(String::as_str).unwrap_or("");
+        let gap = board_columns
+            .saturating_sub(left.chars().count() + right.chars().count())
+            .max(2);
+        queue!(
+            frame,
+            MoveTo(0, row as u16),
+            SetForegroundColor(TEXT_COLOR),
+            Print(format!("{}{}{}", left, " ".repeat(gap), right)),
+            Clear(ClearType::UntilNewLine)
+        )?;
+    }
+
+    for y in 0..height {
+        queue!(frame, MoveTo(0, (HUD_ROWS as i32 + y) as u16))?;
+        let mut current = None;
+        for x in 0..width {
+            match cells[(y * width + x) as usize] {
+                Some((text, color)) => {
+                    if current != Some(color) {
+                        queue!(frame, SetForegroundColor(color))?;
+                        current = Some(color);
+                    }
+                    queue!(frame, Print(text))?;
+                }
+                None => queue!(frame, Print(EMPTY_CELL))?,
+            }
+        }
+        queue!(frame, Clear(ClearType::UntilNewLine))?;
+    }
+
+    // The overlay is written over the middle of the board.
+    let mut lines: Vec<String> = scene.overlay.title().into_iter().collect();
+    lines.extend(scene.overlay.lines());
+    let first_row = (HUD_ROWS as i32 + height / 2 - lines.len() as i32 / 2).max(0);
+    for (row, line) in lines.iter().enumerate() {
+        let column = board_columns.saturating_sub(line.chars().count() + 2) / 2;
+        queue!(
+            frame,
+            MoveTo(column as u16, (first_row + row as i32) as u16),
+            SetForegroundColor(TEXT_COLOR),
+            Print(format!(" {} ", line))
+        )?;
+    }
+    queue!(frame, ResetColor)?;
+    Ok(frame)
+}
@@ -0,0 +1,898 @@
+use std::collections::HashSet;
+
+use rand::seq::{IndexedRandom, IteratorRandom};
+
+use crate::controller::BoardView;
+use crate::difficulty::Difficulty;
+use crate::food::{Food, FoodKind};
+use crate::level::Level;
+use crate::rng::GameRng;
+use crate::save::SaveGame;
+use crate::score::Score;
+use crate::snake::{Direction, Snake, WrapBounds};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameState {
+    Menu,
+    Playing,
+    Paused,
+    GameOver { final_score: u32 },
+    /// The snake filled every free cell of the board.
+    Victory { final_score: u32 },
+}
+
+impl GameState {
+    /// The score a finished game ended on, won or lost.
+    pub fn final_score(&self) -> Option<u32> {
+        match self {
+            GameState::GameOver { final_score } | GameState::Victory { final_score } => Some(*final_score),
+            GameState::Menu | GameState::Playing | GameState::Paused => None,
+        }
+    }
+}
+
+/// What happens at the edge of the board.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum BoardMode {
+    /// Touching the border kills the snake.
+    #[default]
+    Walls,
+    /// The head leaves one edge and comes back in on the opposite one.
+    Wrap,
+}
+
+impl BoardMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoardMode::Walls => "walls",
+            BoardMode::Wrap => "wrap",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<BoardMode> {
+        match name {
+            "walls" => Some(BoardMode::Walls),
+            "wrap" => Some(BoardMode::Wrap),
+            _ => None,
+        }
+    }
+}
+
+/// How many foods are kept on the board at once.
+pub const FOOD_SLOTS: usize = 3;
+
+/// Segments removed by [`FoodKind::Shrink`].
+const SHRINK_LENGTH: usize = 2;
+
+/// Shrinking never takes the snake below its starting length.
+const MIN_LENGTH: usize = 3;
+
+/// How far speed-up and slow-down food can push the speed in either direction.
+pub const MAX_SPEED_LEVEL: i32 = 4;
+
+/// What happened during a single call to [`World::step`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StepOutcome {
+    /// The world is not running, nothing moved.
+    Idle,
+    Moved,
+    Ate(FoodKind),
+    Died,
+    /// The snake grew into the last free cell.
+    Won,
+}
+
+pub struct Grid {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Grid {
+    pub(crate) fn new(width: i32, height: i32) -> Grid {
+        Grid { width, height }
+    }
+
+    /// Whether (x, y) lies inside the border, i.e. on a playable cell.
+    pub fn is_inside(&self, x: i32, y: i32) -> bool {
+        x > 0 && y > 0 && x < self.width - 1 && y < self.height - 1
+    }
+
+    /// The playable cells, for a snake that wraps instead of hitting the border.
+    pub fn wrap_bounds(&self) -> WrapBounds {
+        WrapBounds {
+            min_x: 1,
+            min_y: 1,
+            max_x: self.width - 2,
+            max_y: self.height - 2,
+        }
+    }
+}
+
+/// The rules of the game without any rendering, audio or persistence.
+///
+/// Front ends ask the snake's [`Controller`](crate::controller::Controller)
+/// for a turn and pass it to [`World::step_with`] whenever the snake should
+/// move, then react to the returned [`StepOutcome`].
+pub struct World {
+    snake: Snake,
+    foods: Vec<Food>,
+    growth: u32,
+    speed: i32,
+    score: Score,
+    grid: Grid,
+    level: Level,
+    walls: HashSet<(i32, i32)>,
+    mode: BoardMode,
+    difficulty: Difficulty,
+    state: GameState,
+    rng: GameRng,
+    tick: u64,
+    last_turn: Option<Direction>,
+}
+
+impl World {
+    /// Creates a world whose food placement is fully determined by `seed`.
+    pub fn new(width: i32, height: i32, seed: u64) -> World {
+        World::with_rng(width, height, GameRng::new(seed))
+    }
+
+    /// Creates a world that continues an existing random sequence, e.g. the
+    /// one a recorded game started from.
+    pub fn with_rng(width: i32, height: i32, rng: GameRng) -> World {
+        World::with_level(Level::empty(width, height), rng)
+    }
+
+    /// Creates a world laid out by `level`: its size, walls, spawn and first food.
+    pub fn with_level(level: Level, rng: GameRng) -> World {
+        World {
+            snake: level.spawn_snake(),
+            foods: level.food.map(initial_food).into_iter().collect(),
+            growth: 0,
+            speed: 0,
+            score: Score::new(),
+            grid: Grid::new(level.width, level.height),
+            walls: level.walls.iter().copied().collect(),
+            level,
+            mode: BoardMode::Walls,
+            difficulty: Difficulty::Normal,
+            state: GameState::Menu,
+            rng,
+            tick: 0,
+            last_turn: None,
+        }
+    }
+
+    pub fn snake(&self) -> &Snake {
+        &self.snake
+    }
+
+    pub fn foods(&self) -> &[Food] {
+        &self.foods
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// Whether (x, y) is one of the level's interior walls.
+    pub fn is_wall(&self, x: i32, y: i32) -> bool {
+        self.walls.contains(&(x, y))
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// What a [`Controller`](crate::controller::Controller) sees of the board.
+    pub fn view(&self) -> BoardView<'_> {
+        BoardView::new(
+            &self.grid,
+            self.mode,
+            &self.walls,
+            &self.snake,
+            Vec::new(),
+            &self.foods,
+            self.tick,
+        )
+    }
+
+    pub fn mode(&self) -> BoardMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: BoardMode) {
+        self.mode = mode;
+        let wrap = match mode {
+            BoardMode::Walls => None,
+            BoardMode::Wrap => Some(self.grid.wrap_bounds()),
+        };
+        self.snake.set_wrap(wrap);
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// Picks the speed preset. It only changes timing, which the front end
+    /// reads back through [`World::difficulty`].
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    pub fn rng(&self) -> &GameRng {
+        &self.rng
+    }
+
+    /// The turn applied by the last step, `None` if the snake went straight.
+    pub fn last_turn(&self) -> Option<Direction> {
+        self.last_turn
+    }
+
+    /// Number of logical steps taken since the current game started.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Net effect of the speed-up and slow-down food eaten so far, between
+    /// `-MAX_SPEED_LEVEL` (slowest) and `MAX_SPEED_LEVEL` (fastest).
+    pub fn speed_level(&self) -> i32 {
+        self.speed
+    }
+
+    /// Points earned in the current game. The snake's length is tracked
+    /// separately by [`Snake::len`].
+    pub fn score(&self) -> &Score {
+        &self.score
+    }
+
+    /// Leaves the menu and starts playing. Does nothing in any other state.
+    pub fn start(&mut self) {
+        if self.state == GameState::Menu {
+            self.state = GameState::Playing;
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.state = match self.state {
+            GameState::Playing => GameState::Paused,
+            GameState::Paused => GameState::Playing,
+            ref other => other.clone(),
+        };
+    }
+
+    /// Drops food that runs out before the next tick and tops the board up
+    /// again. This is the only place food is drawn during play, once per
+    /// tick, so the window, bots and replays all draw the same food.
+    fn refresh_food(&mut self) {
+        let tick = self.tick;
+        self.foods.retain(|food| !food.is_expired(tick));
+        self.replenish_food();
+    }
+
+    /// Tops the board up to [`FOOD_SLOTS`] foods.
+    fn replenish_food(&mut self) {
+        if self.state != GameState::Playing {
+            return;
+        }
+        while self.foods.len() < FOOD_SLOTS && self.add_food() {}
+    }
+
+    /// Moves the snake one cell, taking `turn` first if there is one.
+    ///
+    /// A turn into the opposite direction is ignored and the snake keeps going.
+    pub fn step_with(&mut self, turn: Option<Direction>) -> StepOutcome {
+        if self.state != GameState::Playing {
+            return StepOutcome::Idle;
+        }
+
+        self.tick += 1;
+        let direction = turn.filter(|dir| {
+            *dir != self.snake.head_direction() && *dir != self.snake.head_direction().opposite()
+        });
+        self.last_turn = direction;
+
+        if !self.check_if_snake_alive(direction) {
+            self.state = GameState::GameOver {
+                final_score: self.score.points(),
+            };
+            return StepOutcome::Died;
+        }
+
+        self.snake.move_forward(direction);
+        let eaten = self.check_eating();
+        if self.snake.len() >= self.playable_cells() {
+            self.state = GameState::Victory {
+                final_score: self.score.points(),
+            };
+            return StepOutcome::Won;
+        }
+        self.refresh_food();
+        match eaten {
+            Some(kind) => StepOutcome::Ate(kind),
+            None => StepOutcome::Moved,
+        }
+    }
+
+    /// Cells inside the border that are not walls: what the snake has to
+    /// fill to win.
+    pub fn playable_cells(&self) -> usize {
+        let inside = ((self.grid.width - 2) * (self.grid.height - 2)).max(0) as usize;
+        let walls = self.walls.iter().filter(|(x, y)| self.grid.is_inside(*x, *y)).count();
+        inside - walls
+    }
+
+    pub fn restart(&mut self) {
+        self.snake = self.level.spawn_snake();
+        self.set_mode(self.mode);
+        self.foods = self.level.food.map(initial_food).into_iter().collect();
+        self.growth = 0;
+        self.speed = 0;
+        self.score = Score::new();
+        self.state = GameState::Playing;
+        self.tick = 0;
+        self.last_turn = None;
+    }
+
+    /// Captures the complete world state for [`crate::save`].
+    pub fn to_save(&self) -> SaveGame {
+        SaveGame {
+            width: self.grid.width,
+            height: self.grid.height,
+            state: self.state.clone(),
+            seed: self.rng.seed(),
+            rng_state: self.rng.state(),
+            tick: self.tick,
+            mode: self.mode,
+            difficulty: self.difficulty,
+            direction: self.snake.head_direction(),
+            snake: self.snake.body_positions(),
+            foods: self.foods.clone(),
+            growth: self.growth,
+            speed: self.speed,
+            score: self.score.clone(),
+            level: self.level.clone(),
+        }
+    }
+
+    /// Rebuilds a world from a save so that it continues tick for tick
+    /// exactly like the one it was taken from.
+    pub fn from_save(save: &SaveGame) -> World {
+        let mut world = World {
+            snake: Snake::from_parts(&save.snake, save.direction),
+            foods: save.foods.clone(),
+            growth: save.growth,
+            speed: save.speed,
+            score: save.score.clone(),
+            grid: Grid::new(save.width, save.height),
+            walls: save.level.walls.iter().copied().collect(),
+            level: save.level.clone(),
+            mode: save.mode,
+            difficulty: save.difficulty,
+            state: save.state.clone(),
+            rng: GameRng::from_state(save.seed, save.rng_state),
+            tick: save.tick,
+            last_turn: None,
+        };
+        world.set_mode(save.mode);
+        world
+    }
+
+    /// Replaces all food on the board with a single normal food at (x, y).
+    pub fn set_food_position(&mut self, x: i32, y: i32) {
+        self.foods.clear();
+        self.place_food(FoodKind::Normal, x, y);
+    }
+
+    /// Adds a food of `kind` at (x, y), replacing any food already there.
+    pub fn place_food(&mut self, kind: FoodKind, x: i32, y: i32) {
+        self.foods.retain(|food| food.position() != (x, y));
+        let expires_at = kind.lifetime().map(|lifetime| self.tick + lifetime);
+        self.foods.push(Food::new(kind, x, y, expires_at));
+    }
+
+    /// Eats the food under the head, if any, and applies its effect. Growth
+    /// is paid out one segment per tick, starting with this one.
+    pub(crate) fn check_eating(&mut self) -> Option<FoodKind> {
+        let head = self.snake.head_position();
+        let eaten = self
+            .foods
+            .iter()
+            .position(|food| food.position() == head)
+            .map(|index| self.foods.remove(index));
+
+        if let Some(food) = &eaten {
+            self.score.eat(food, self.tick, self.speed);
+            apply_food(food.kind(), &mut self.snake, &mut self.growth, &mut self.speed);
+        }
+
+        pay_out_growth(&mut self.snake, &mut self.growth);
+        eaten.map(|food| food.kind())
+    }
+
+    pub(crate) fn check_if_snake_alive(&self, dir: Option<Direction>) -> bool {
+        let (next_x, next_y) = self.snake.next_head(dir);
+
+        if self.snake.overlap_tail(next_x, next_y) || self.is_wall(next_x, next_y) {
+            return false;
+        }
+
+        self.grid.is_inside(next_x, next_y)
+    }
+
+    /// Places one food of a weighted random kind on a free cell. Returns
+    /// false if the board has no free cell left.
+    pub(crate) fn add_food(&mut self) -> bool {
+        let snake = &self.snake;
+        let walls = &self.walls;
+        let foods = &self.foods;
+        let choice = (1..self.grid.width - 1)
+            .flat_map(|x| (1..self.grid.height - 1).map(move |y| (x, y))) // grid of all possible positions
+            .filter(|(x, y)| !snake.contains(*x, *y)) // don't intersect snake, head included
+            .filter(|cell| !walls.contains(cell)) // nor a wall
+            .filter(|cell| foods.iter().all(|food| food.position() != *cell)) // nor other food
+            .choose(&mut self.rng);
+
+        let Some((new_x, new_y)) = choice else {
+            return false;
+        };
+        let kind = *FoodKind::ALL
+            .choose_weighted(&mut self.rng, FoodKind::spawn_weight)
+            .expect("food weights are positive");
+        self.place_food(kind, new_x, new_y);
+        true
+    }
+}
+
+/// The food a level starts with is always plain food.
+pub(crate) fn initial_food((x, y): (i32, i32)) -> Food {
+    Food::new(FoodKind::Normal, x, y, None)
+}
+
+/// Applies the effect of eating a food of `kind`, except for the score.
+pub(crate) fn apply_food(kind: FoodKind, snake: &mut Snake, growth: &mut u32, speed: &mut i32) {
+    *growth += kind.growth();
+    match kind {
+        FoodKind::Shrink => {
+            *growth = 0;
+            for _ in 0..SHRINK_LENGTH {
+                if snake.len() <= MIN_LENGTH {
+                    break;
+                }
+                snake.remove_tail();
+            }
+        }
+        FoodKind::SpeedUp => *speed = (*speed + 1).min(MAX_SPEED_LEVEL),
+        FoodKind::SlowDown => *speed = (*speed - 1).max(-MAX_SPEED_LEVEL),
+        FoodKind::Normal | FoodKind::Bonus => {}
+    }
+}
+
+/// Grows the snake by one pending segment, if any.
+pub(crate) fn pay_out_growth(snake: &mut Snake, growth: &mut u32) {
+    if *growth > 0 {
+        *growth -= 1;
+        snake.restore_tail();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn playing_world(width: i32, height: i32) -> World {
+        let mut world = World::new(width, height, 0);
+        world.start();
+        world
+    }
+
+    #[test]
+    fn new_creates_world_with_correct_dimensions() {
+        let world = World::new(15, 15, 0);
+        assert_eq!(world.grid().width, 15);
+        assert_eq!(world.grid().height, 15);
+    }
+
+    #[test]
+    fn new_world_starts_in_menu() {
+        let world = World::new(15, 15, 0);
+        assert_eq!(world.state(), &GameState::Menu);
+    }
+
+    #[test]
+    fn new_world_has_food() {
+        let world = World::new(15, 15, 0);
+        assert!(!world.foods().is_empty());
+    }
+
+    #[test]
+    fn step_does_nothing_in_menu() {
+        let mut world = World::new(15, 15, 0);
+        let before = world.snake().head_position();
+        assert_eq!(world.step_with(None), StepOutcome::Idle);
+        assert_eq!(world.snake().head_position(), before);
+    }
+
+    #[test]
+    fn toggle_pause_switches_between_playing_and_paused() {
+        let mut world = playing_world(15, 15);
+        world.toggle_pause();
+        assert_eq!(world.state(), &GameState::Paused);
+        world.toggle_pause();
+        assert_eq!(world.state(), &GameState::Playing);
+    }
+
+    #[test]
+    fn step_counts_ticks_only_while_playing() {
+        let mut world = World::new(15, 15, 0);
+        world.step_with(None);
+        assert_eq!(world.tick(), 0);
+
+        world.start();
+        world.step_with(None);
+        world.step_with(None);
+        assert_eq!(world.tick(), 2);
+    }
+
+    #[test]
+    fn step_with_applies_turn_directly() {
+        let mut world = playing_world(15, 15);
+        world.step_with(Some(Direction::Down));
+        assert_eq!(world.snake().head_position(), (4, 3));
+        assert_eq!(world.last_turn(), Some(Direction::Down));
+
+        world.step_with(None);
+        assert_eq!(world.last_turn(), None);
+    }
+
+    #[test]
+    fn step_replaces_eaten_food_after_moving() {
+        let mut world = playing_world(15, 15);
+        world.set_food_position(5, 2);
+        assert_eq!(world.step_with(None), StepOutcome::Ate(FoodKind::Normal));
+        assert_eq!(world.foods().len(), FOOD_SLOTS);
+        assert!(world.foods().iter().all(|food| food.position() != (5, 2)));
+    }
+
+    #[test]
+    fn snake_dies_hitting_top_wall() {
+        let mut world = playing_world(15, 15);
+        let mut outcome = StepOutcome::Moved;
+        for _ in 0..10 {
+            outcome = world.step_with(Some(Direction::Up));
+            if outcome == StepOutcome::Died {
+                break;
+            }
+        }
+
+        assert_eq!(outcome, StepOutcome::Died);
+        assert_eq!(world.state(), &GameState::GameOver { final_score: 0 });
+    }
+
+    #[test]
+    fn wrap_mode_brings_snake_back_on_the_other_side() {
+        let mut world = playing_world(15, 15);
+        world.set_mode(BoardMode::Wrap);
+        assert_eq!(world.step_with(Some(Direction::Up)), StepOutcome::Moved);
+        assert_eq!(world.snake().head_position(), (4, 1));
+        assert_eq!(world.step_with(None), StepOutcome::Moved);
+        assert_eq!(world.snake().head_position(), (4, 13));
+    }
+
+    #[test]
+    fn wrap_mode_survives_restart() {
+        let mut world = playing_world(15, 15);
+        world.set_mode(BoardMode::Wrap);
+        world.restart();
+        for _ in 0..20 {
+            assert_ne!(world.step_with(None), StepOutcome::Died);
+        }
+    }
+
+    #[test]
+    fn board_mode_names_roundtrip() {
+        for mode in [BoardMode::Walls, BoardMode::Wrap] {
+            assert_eq!(BoardMode::from_name(mode.name()), Some(mode));
+        }
+    }
+
+    #[test]
+    fn check_if_snake_alive_returns_true_initially() {
+        let world = World::new(15, 15, 0);
+        assert!(world.check_if_snake_alive(None));
+    }
+
+    #[test]
+    fn eating_food_grows_snake() {
+        let mut world = playing_world(15, 15);
+        // Snake head at (4,2), moving right
+        world.set_food_position(5, 2);
+
+        assert_eq!(world.step_with(None), StepOutcome::Ate(FoodKind::Normal));
+        assert_eq!(world.snake().len(), 4);
+        assert!(world.foods().iter().all(|food| food.position() != (5, 2)));
+    }
+
+    #[test]
+    fn add_food_places_food_in_bounds() {
+        let mut world = playing_world(15, 15);
+        world.foods.clear();
+        world.add_food();
+
+        let (x, y) = world.foods()[0].position();
+        assert!(x > 0 && x < 14);
+        assert!(y > 0 && y < 14);
+    }
+
+    #[test]
+    fn same_seed_places_same_food() {
+        let mut a = playing_world(15, 15);
+        let mut b = playing_world(15, 15);
+        for _ in 0..20 {
+            a.add_food();
+            b.add_food();
+            assert_eq!(a.foods(), b.foods());
+        }
+    }
+
+    #[test]
+    fn different_seeds_place_different_food() {
+        let mut a = World::new(15, 15, 1);
+        let mut b = World::new(15, 15, 2);
+        let sequence = |world: &mut World| {
+            (0..20)
+                .map(|_| {
+                    world.add_food();
+                    world.foods().last().map(Food::position)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_ne!(sequence(&mut a), sequence(&mut b));
+    }
+
+    #[test]
+    fn save_roundtrip_produces_identical_ticks() {
+        let turns = [Direction::Down, Direction::Right, Direction::Up, Direction::Right];
+        let mut original = World::new(20, 20, 99);
+        original.set_mode(BoardMode::Wrap);
+        original.start();
+        for _ in 0..5 {
+            original.step_with(None);
+        }
+
+        let mut resumed = World::from_save(&SaveGame::decode(&original.to_save().encode()).unwrap());
+
+        for tick in 0..40 {
+            let turn = (tick % 4 == 0).then(|| turns[(tick / 4) % turns.len()]);
+            assert_eq!(original.step_with(turn), resumed.step_with(turn));
+            assert_eq!(original.to_save(), resumed.to_save());
+        }
+    }
+
+    #[test]
+    fn restart_resets_world() {
+        let mut world = playing_world(15, 15);
+        world.set_food_position(5, 2);
+        world.step_with(None);
+        world.state = GameState::GameOver { final_score: 0 };
+        world.foods.clear();
+
+        world.restart();
+
+        assert_eq!(world.state(), &GameState::Playing);
+        assert!(!world.foods().is_empty());
+        assert_eq!(world.snake().len(), 3);
+        assert_eq!(world.score(), &Score::new());
+    }
+
+    fn walled_world() -> World {
+        let level = Level::parse("map\n########\n#..@#..#\n#......#\n#..*...#\n########\n").unwrap();
+        let mut world = World::with_level(level, GameRng::new(0));
+        world.start();
+        world
+    }
+
+    #[test]
+    fn level_sets_spawn_and_food() {
+        let world = walled_world();
+        assert_eq!((world.grid().width, world.grid().height), (8, 5));
+        assert_eq!(world.snake().head_position(), (3, 1));
+        assert_eq!(world.foods(), [Food::new(FoodKind::Normal, 3, 3, None)]);
+        assert!(world.is_wall(4, 1));
+    }
+
+    #[test]
+    fn playable_cells_leave_out_walls() {
+        assert_eq!(walled_world().playable_cells(), 17);
+        assert_eq!(World::new(15, 15, 0).playable_cells(), 13 * 13);
+    }
+
+    #[test]
+    fn filling_the_board_wins() {
+        let level = Level::parse("direction right\nmap\n#######\n#..@*.#\n#######\n").unwrap();
+        let mut world = World::with_level(level, GameRng::new(0));
+        world.start();
+        assert_eq!(world.step_with(None), StepOutcome::Ate(FoodKind::Normal));
+        world.foods.clear();
+        world.place_food(FoodKind::Normal, 5, 1);
+        assert_eq!(world.step_with(None), StepOutcome::Won);
+        assert_eq!(world.snake().len(), 5);
+        let points = world.score().points();
+        assert_eq!(world.state(), &GameState::Victory { final_score: points });
+        assert_eq!(world.state().final_score(), Some(points));
+        assert_eq!(world.step_with(None), StepOutcome::Idle);
+    }
+
+    #[test]
+    fn hitting_an_interior_wall_ends_the_game() {
+        let mut world = walled_world();
+        assert_eq!(world.step_with(None), StepOutcome::Died);
+    }
+
+    #[test]
+    fn walls_also_kill_in_wrap_mode() {
+        let mut world = walled_world();
+        world.set_mode(BoardMode::Wrap);
+        assert_eq!(world.step_with(None), StepOutcome::Died);
+    }
+
+    #[test]
+    fn add_food_avoids_walls() {
+        let mut world = walled_world();
+        while world.add_food() {}
+
+        // 18 interior cells, one wall and three snake segments.
+        assert_eq!(world.foods().len(), 14);
+        for food in world.foods() {
+            let (x, y) = food.position();
+            assert!(!world.is_wall(x, y));
+        }
+    }
+
+    #[test]
+    fn add_food_never_lands_on_the_head() {
+        let mut world = walled_world();
+        let head = world.snake().head_position();
+        while world.add_food() {}
+        assert!(world.foods().iter().all(|food| food.position() != head));
+    }
+
+    #[test]
+    fn restart_returns_to_level_spawn() {
+        let mut world = walled_world();
+        world.step_with(Some(Direction::Down));
+        world.restart();
+        assert_eq!(world.snake().head_position(), (3, 1));
+        assert_eq!(world.foods(), [Food::new(FoodKind::Normal, 3, 3, None)]);
+    }
+
+    #[test]
+    fn save_keeps_level() {
+        let world = walled_world();
+        let resumed = World::from_save(&world.to_save());
+        assert!(resumed.is_wall(4, 1));
+        assert_eq!(resumed.level(), world.level());
+    }
+
+    #[test]
+    fn bonus_food_grows_one_segment_per_tick() {
+        let mut world = playing_world(15, 15);
+        world.foods.clear();
+        world.place_food(FoodKind::Bonus, 5, 2);
+
+        assert_eq!(world.step_with(None), StepOutcome::Ate(FoodKind::Bonus));
+        assert_eq!(world.snake().len(), 4);
+        world.step_with(Some(Direction::Down));
+        world.step_with(None);
+        assert_eq!(world.snake().len(), 6);
+        world.step_with(None);
+        assert_eq!(world.snake().len(), 6);
+    }
+
+    #[test]
+    fn shrink_food_takes_segments_off_the_tail() {
+        let mut world = playing_world(15, 15);
+        world.foods.clear();
+        for x in 5..8 {
+            world.place_food(FoodKind::Normal, x, 2);
+        }
+        world.place_food(FoodKind::Shrink, 8, 2);
+        for _ in 0..3 {
+            world.step_with(None);
+        }
+        assert_eq!(world.snake().len(), 6);
+
+        assert_eq!(world.step_with(None), StepOutcome::Ate(FoodKind::Shrink));
+        assert_eq!(world.snake().len(), 4);
+    }
+
+    #[test]
+    fn shrink_food_keeps_the_starting_length() {
+        let mut world = playing_world(15, 15);
+        world.foods.clear();
+        world.place_food(FoodKind::Shrink, 5, 2);
+        world.step_with(None);
+        assert_eq!(world.snake().len(), MIN_LENGTH);
+    }
+
+    #[test]
+    fn speed_food_changes_speed_level_within_bounds() {
+        let mut world = playing_world(40, 5);
+        world.foods.clear();
+        for x in 5..11 {
+            world.place_food(FoodKind::SpeedUp, x, 2);
+        }
+        world.place_food(FoodKind::SlowDown, 11, 2);
+        for _ in 0..6 {
+            world.step_with(None);
+        }
+        assert_eq!(world.speed_level(), MAX_SPEED_LEVEL);
+
+        world.step_with(None);
+        assert_eq!(world.speed_level(), MAX_SPEED_LEVEL - 1);
+    }
+
+    #[test]
+    fn expired_food_disappears() {
+        let mut world = playing_world(40, 5);
+        world.foods.clear();
+        world.place_food(FoodKind::Bonus, 10, 3);
+
+        for _ in 1..FoodKind::Bonus.lifetime().unwrap() {
+            world.step_with(None);
+        }
+        assert!(world.foods().iter().any(|food| food.position() == (10, 3)));
+        world.step_with(None);
+        assert!(world.foods().iter().all(|food| food.position() != (10, 3)));
+    }
+
+    #[test]
+    fn board_keeps_several_foods() {
+        let mut world = playing_world(15, 15);
+        world.step_with(None);
+        assert_eq!(world.foods().len(), FOOD_SLOTS);
+    }
+
+    #[test]
+    fn spawned_kinds_follow_the_weights() {
+        let mut world = playing_world(20, 20);
+        world.foods.clear();
+        while world.add_food() {}
+
+        let count = |kind| world.foods().iter().filter(|food| food.kind() == kind).count();
+        assert!(count(FoodKind::Normal) > count(FoodKind::Bonus));
+        for kind in FoodKind::ALL {
+            assert!(count(kind) > 0, "no {:?} food spawned", kind);
+        }
+    }
+
+    #[test]
+    fn eating_scores_points_apart_from_length() {
+        let mut world = playing_world(15, 15);
+        world.set_food_position(5, 2);
+        world.step_with(None);
+        assert_eq!(world.score().points(), FoodKind::Normal.points());
+        assert_eq!(world.snake().len(), 4);
+    }
+
+    #[test]
+    fn game_over_keeps_the_points() {
+        let mut world = playing_world(15, 15);
+        world.set_food_position(5, 2);
+        world.step_with(None);
+        while world.step_with(Some(Direction::Up)) != StepOutcome::Died {}
+        assert_eq!(
+            world.state(),
+            &GameState::GameOver {
+                final_score: FoodKind::Normal.points()
+            }
+        );
+    }
+}